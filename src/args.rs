@@ -1,6 +1,70 @@
-use clap::{Arg, Command};
+use std::path::PathBuf;
+use clap::{builder::PossibleValuesParser, Arg, ArgAction, Command};
+use crate::audio::SAMPLE_RATE;
+use crate::calibration::{self, MAX_LATENCY_MS};
+use crate::drone::{Drone, Note, MAX_REFERENCE, MIN_REFERENCE, WAVEFORMS};
+use crate::gap::GapArgs;
+use crate::ladder::LadderArgs;
+use crate::meter::{self, Displacement, Polymeter, Polyrhythm, TimeSignature};
+use crate::output;
+use crate::patterns;
+use crate::ramp::{Curve, CURVES};
+use crate::state::{Count, Pan, MAX_SWING, MIN_SWING};
+use crate::synth::VoiceSpec;
 
-pub fn parse_arguments() -> (f64, f64, Option<f64>, Option<u32>) {
+#[derive(Clone)]
+pub struct Args {
+    pub start_bpm: f64,
+    /// Tempos a progressive ramp passes through: the start and end BPM, or every tempo
+    /// of a multi-segment ramp.
+    pub tempos: Vec<f64>,
+    /// Times the progressive ramp is played; 0 repeats it until quit.
+    pub cycles: u32,
+    /// Speed-trainer ladder that sets the tempo instead of a ramp.
+    pub ladder: Option<LadderArgs>,
+    /// Gap-click trainer dropping the click out for whole bars.
+    pub gap: Option<GapArgs>,
+    pub duration: Option<f64>,
+    pub measures: Option<u32>,
+    /// Shape of the progressive ramp.
+    pub curve: Curve,
+    /// Bars played in order; a single bar for a plain time signature.
+    pub bars: Vec<TimeSignature>,
+    pub loop_bars: bool,
+    pub subdivision: u32,
+    pub swing: f64,
+    pub displacement: Displacement,
+    pub polyrhythm: Option<Polyrhythm>,
+    pub polymeter: Option<Polymeter>,
+    /// Index into `PATTERNS` of the rhythm pattern to start with.
+    pub pattern: Option<usize>,
+    pub accent_voice: VoiceSpec,
+    pub beat_voice: VoiceSpec,
+    pub subdivision_voice: VoiceSpec,
+    pub count: Count,
+    /// Directory of recordings replacing the built-in counting voice.
+    pub count_voice: Option<PathBuf>,
+    /// Master volume from 0 to 1.
+    pub volume: f32,
+    pub pan: Pan,
+    /// Reference tone, playing from the start if a note was given.
+    pub drone: Drone,
+    /// Drone level from 0 to 1.
+    pub drone_level: f32,
+    /// Audio latency in milliseconds that the display is delayed by.
+    pub latency: f64,
+    /// WAV file to render the session to instead of playing it.
+    pub render: Option<PathBuf>,
+    pub sample_rate: u32,
+    /// Length of the rendered file in seconds; until the session ends when not given.
+    pub length: Option<f64>,
+    /// Output device name; the system default when not given.
+    pub device: Option<String>,
+    /// Run the metronome without audio, showing the beats only.
+    pub silent: bool,
+}
+
+pub fn parse_arguments() -> Args {
     let matches = Command::new("Metronome")
         .version("1.1")
         .about("A simple TUI metronome that can progressively speed up")
@@ -9,7 +73,7 @@ pub fn parse_arguments() -> (f64, f64, Option<f64>, Option<u32>) {
                 .short('s')
                 .long("start-bpm")
                 .help("Starting BPM")
-                .required(true),
+                .required_unless_present_any(["list-devices", "ramp"]),
         )
         .arg(
             Arg::new("end-bpm")
@@ -18,11 +82,58 @@ pub fn parse_arguments() -> (f64, f64, Option<f64>, Option<u32>) {
                 .help("Ending BPM")
                 .required(false),
         )
+        .arg(
+            Arg::new("ramp")
+                .long("ramp")
+                .help("Comma separated tempos a progressive ramp passes through, e.g., 60,120,60 to go up and back down. Each segment lasts --duration")
+                .conflicts_with_all(["start-bpm", "end-bpm"])
+                .requires("duration"),
+        )
+        .arg(
+            Arg::new("cycles")
+                .long("cycles")
+                .help("Number of times to play the progressive ramp, or 0 to repeat it until you quit")
+                .requires("duration")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::new("ladder")
+                .long("ladder")
+                .help("Speed-trainer ladder up to this final target BPM: climb from --start-bpm in steps, drop back at each peak and finish once the target has been played")
+                .conflicts_with_all(["end-bpm", "duration", "ramp"]),
+        )
+        .arg(
+            Arg::new("ladder-step")
+                .long("ladder-step")
+                .help("BPM gained on each rung of the ladder")
+                .requires("ladder")
+                .default_value("5"),
+        )
+        .arg(
+            Arg::new("ladder-bars")
+                .long("ladder-bars")
+                .help("Bars played on each rung of the ladder")
+                .requires("ladder")
+                .default_value("4"),
+        )
+        .arg(
+            Arg::new("ladder-setback")
+                .long("ladder-setback")
+                .help("BPM the ladder drops back by after reaching a peak")
+                .requires("ladder")
+                .default_value("10"),
+        )
+        .arg(
+            Arg::new("ladder-ceiling")
+                .long("ladder-ceiling")
+                .help("Peak of the ladder's first climb in BPM. Every later climb peaks one setback higher [default: one setback below the target]")
+                .requires("ladder"),
+        )
         .arg(
             Arg::new("duration")
                 .short('d')
                 .long("duration")
-                .help("Duration over which BPM changes (in seconds)")
+                .help("Duration over which BPM changes (in seconds), per segment of a --ramp")
                 .required(false),
         )
         .arg(
@@ -32,32 +143,580 @@ pub fn parse_arguments() -> (f64, f64, Option<f64>, Option<u32>) {
                 .help("Number of beats per BPM increment. Should be a multiple of the meter, e.g., 4, 32, 64, etc.")
                 .required(false),
         )
+        .arg(
+            Arg::new("curve")
+                .long("curve")
+                .help("Shape of the progressive tempo change. smooth changes the tempo on every beat and needs no --measures")
+                .value_parser(PossibleValuesParser::new(CURVES.map(|(name, _)| name)))
+                .default_value("linear"),
+        )
+        .arg(
+            Arg::new("time-signature")
+                .short('t')
+                .long("time-signature")
+                .help("Time signature, e.g., 4/4, 3/4, 7/8 or 3+3+2/8. BPM counts quarter notes, so eighth-note meters tick twice as fast")
+                .default_value("4/4"),
+        )
+        .arg(
+            Arg::new("bars")
+                .short('b')
+                .long("bars")
+                .help("Comma separated bar sequence, e.g., \"4/4,3/4,5/8,7/8\". Loops unless --once is given")
+                .conflicts_with_all(["time-signature", "polyrhythm", "polymeter"])
+                .required(false),
+        )
+        .arg(
+            Arg::new("once")
+                .short('o')
+                .long("once")
+                .help("Play the --bars sequence a single time and then stop")
+                .requires("bars")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pattern")
+                .short('r')
+                .long("pattern")
+                .help("Play a named rhythm pattern in its own meter instead of the plain pulse")
+                .value_parser(PossibleValuesParser::new(patterns::names()))
+                .required(false),
+        )
+        .arg(
+            Arg::new("subdivision")
+                .short('u')
+                .long("subdivision")
+                .help("Clicks per beat, e.g., 2 for eighths, 3 for triplets, 4 for sixteenths")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::new("swing")
+                .short('w')
+                .long("swing")
+                .help("Swing percentage for subdivisions, from 50 (straight) to 75 (hard shuffle)")
+                .default_value("50"),
+        )
+        .arg(
+            Arg::new("accent-voice")
+                .long("accent-voice")
+                .help("Voice for accented clicks: tick, sine, square, woodblock, cowbell, rimshot, hihat or clack, optionally with parameters, e.g., \"cowbell:pitch=600,decay=0.2,level=0.8\"")
+                .default_value("tick:pitch=1.5"),
+        )
+        .arg(
+            Arg::new("accent-sample")
+                .long("accent-sample")
+                .help("WAV, OGG or FLAC file to play for accented clicks instead of --accent-voice")
+                .conflicts_with("accent-voice")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("beat-voice")
+                .long("beat-voice")
+                .help("Voice for normal beats, same format as --accent-voice")
+                .default_value("tick"),
+        )
+        .arg(
+            Arg::new("beat-sample")
+                .long("beat-sample")
+                .help("WAV, OGG or FLAC file to play for normal beats instead of --beat-voice")
+                .conflicts_with("beat-voice")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("subdivision-voice")
+                .long("subdivision-voice")
+                .help("Voice for subdivision clicks, same format as --accent-voice. Plays at level 0.4 unless a level is given")
+                .default_value("tick"),
+        )
+        .arg(
+            Arg::new("subdivision-sample")
+                .long("subdivision-sample")
+                .help("WAV, OGG or FLAC file to play for subdivision clicks instead of --subdivision-voice")
+                .conflicts_with("subdivision-voice")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("count")
+                .long("count")
+                .help("Speak the count over the clicks: the beat numbers, or the numbers with \"e\", \"and\" and \"a\" on eighths, triplets and sixteenths")
+                .value_parser(PossibleValuesParser::new(["beats", "subdivisions"]))
+                .required(false),
+        )
+        .arg(
+            Arg::new("count-voice")
+                .long("count-voice")
+                .help("Directory of WAV, OGG or FLAC recordings replacing the built-in counting voice, named 1.wav, 2.wav, ..., and.wav, e.wav and a.wav")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("volume")
+                .short('v')
+                .long("volume")
+                .help("Master volume in percent, from 0 to 100")
+                .default_value("100"),
+        )
+        .arg(
+            Arg::new("pan")
+                .long("pan")
+                .help("Stereo position of each click layer from -1 (left) to 1 (right), e.g., \"accent=0,beat=-1,layer=1\". Layers are accent, beat, subdivision, layer and count")
+                .required(false),
+        )
+        .arg(
+            Arg::new("drone")
+                .long("drone")
+                .help("Start with a sustained reference pitch under the click, e.g., A4, C#3 or Bb2. Press T to start or stop the drone [default note: A4]")
+                .num_args(0..=1)
+                .default_missing_value("A4")
+                .required(false),
+        )
+        .arg(
+            Arg::new("reference")
+                .long("reference")
+                .help("Frequency of A4 in Hz that the drone is tuned to, from 400 to 480")
+                .default_value("440"),
+        )
+        .arg(
+            Arg::new("drone-wave")
+                .long("drone-wave")
+                .help("Waveform of the drone")
+                .value_parser(PossibleValuesParser::new(WAVEFORMS.map(|(name, _)| name)))
+                .default_value("sine"),
+        )
+        .arg(
+            Arg::new("drone-level")
+                .long("drone-level")
+                .help("Drone volume in percent, from 0 to 100")
+                .default_value("30"),
+        )
+        .arg(
+            Arg::new("latency")
+                .long("latency")
+                .help("Audio output latency in milliseconds. The display is delayed by this much to line up with the click. Defaults to the last calibrated value")
+                .required(false),
+        )
+        .arg(
+            Arg::new("render")
+                .long("render")
+                .help("Write the session to a WAV file as fast as possible instead of playing it")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("sample-rate")
+                .long("sample-rate")
+                .help("Sample rate of the rendered file in Hz [default: 44100]")
+                .requires("render"),
+        )
+        .arg(
+            Arg::new("length")
+                .long("length")
+                .help("Length of the rendered file in seconds. Defaults to the full length of a progressive ramp with a set number of cycles, or the end of a ladder or a --once sequence")
+                .requires("render"),
+        )
+        .arg(
+            Arg::new("device")
+                .long("device")
+                .help("Name of the audio output device, as shown by --list-devices")
+                .required(false),
+        )
+        .arg(
+            Arg::new("silent")
+                .long("silent")
+                .help("Run without audio and only show the beats. Used automatically when no audio device is available")
+                .conflicts_with_all(["device", "render"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("list-devices")
+                .long("list-devices")
+                .help("List the audio output devices and exit")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("gap")
+                .long("gap")
+                .help("Gap-click trainer: play the click for some bars, then leave some bars silent while the counter keeps running, e.g., 4:2")
+                .required(false),
+        )
+        .arg(
+            Arg::new("gap-grow")
+                .long("gap-grow")
+                .help("Silent bars added to the gap after each one")
+                .requires("gap")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("displace")
+                .short('x')
+                .long("displace")
+                .help("Shift every click by a fraction of a beat, e.g., 1/2 to click only on the \"and\"s")
+                .required(false),
+        )
+        .arg(
+            Arg::new("polyrhythm")
+                .short('p')
+                .long("polyrhythm")
+                .help("Play two layers against each other in one bar, e.g., 3:2, 4:3, 5:4, 7:4. The first number sets the beats per bar")
+                .conflicts_with("time-signature")
+                .required(false),
+        )
+        .arg(
+            Arg::new("polymeter")
+                .short('y')
+                .long("polymeter")
+                .help("Bar length in beats of a second layer on the same pulse, e.g., 5 against --time-signature 4/4")
+                .conflicts_with("polyrhythm")
+                .required(false),
+        )
         .get_matches();
 
-    let start_bpm = matches
-        .get_one::<String>("start-bpm")
-        .expect("Invalid starting BPM")
-        .parse::<f64>()
-        .expect("Invalid starting BPM");
+    if matches.get_flag("list-devices") {
+        for name in output::device_names() {
+            println!("{name}");
+        }
+        std::process::exit(0);
+    }
 
-    let end_bpm = matches
-        .get_one::<String>("end-bpm")
-        .unwrap_or(&start_bpm.to_string())
-        .parse::<f64>()
-        .expect("Invalid ending BPM");
+    let tempos = matches.get_one::<String>("ramp").map_or_else(
+        || {
+            let start_bpm = matches
+                .get_one::<String>("start-bpm")
+                .expect("Invalid starting BPM")
+                .parse::<f64>()
+                .expect("Invalid starting BPM");
+            let end_bpm = matches
+                .get_one::<String>("end-bpm")
+                .unwrap_or(&start_bpm.to_string())
+                .parse::<f64>()
+                .expect("Invalid ending BPM");
+            vec![start_bpm, end_bpm]
+        },
+        |ramp| {
+            ramp.split(',')
+                .map(|bpm| bpm.trim().parse::<f64>().expect("Invalid ramp tempo"))
+                .collect()
+        },
+    );
+
+    if tempos.len() < 2 {
+        eprintln!("Error: --ramp needs at least two tempos, e.g., 60,120.");
+        std::process::exit(1);
+    }
+    if tempos.iter().any(|&bpm| bpm <= 0.0) {
+        eprintln!("Error: Tempos must be greater than zero.");
+        std::process::exit(1);
+    }
+    let start_bpm = tempos[0];
+
+    let cycles = matches
+        .get_one::<String>("cycles")
+        .expect("Invalid cycles")
+        .parse::<u32>()
+        .expect("Invalid cycles");
+
+    let ladder = matches.get_one::<String>("ladder").map(|target| {
+        let bpm = |name: &str| {
+            matches
+                .get_one::<String>(name)
+                .expect("Invalid ladder setting")
+                .parse::<f64>()
+                .expect("Invalid ladder setting")
+        };
+        let target = target.parse::<f64>().expect("Invalid ladder target");
+        let (step, setback) = (bpm("ladder-step"), bpm("ladder-setback"));
+        LadderArgs {
+            start_bpm,
+            step,
+            bars: matches
+                .get_one::<String>("ladder-bars")
+                .expect("Invalid ladder bars")
+                .parse::<u32>()
+                .expect("Invalid ladder bars"),
+            setback,
+            // By default the ladder drops back once, a setback short of the target.
+            ceiling: matches.get_one::<String>("ladder-ceiling").map_or_else(
+                || (target - setback).max(start_bpm + step).min(target),
+                |ceiling| ceiling.parse::<f64>().expect("Invalid ladder ceiling"),
+            ),
+            target,
+        }
+    });
+
+    if let Some(ladder) = &ladder {
+        if ladder.target <= start_bpm || ladder.ceiling <= start_bpm {
+            eprintln!("Error: The --ladder target and --ladder-ceiling must be faster than --start-bpm.");
+            std::process::exit(1);
+        }
+        if ladder.step <= 0.0 || ladder.bars == 0 {
+            eprintln!("Error: --ladder-step and --ladder-bars must be greater than zero.");
+            std::process::exit(1);
+        }
+        // Each setback raises the next peak by the same amount, so without one the ladder
+        // would never get past its ceiling.
+        if ladder.ceiling < ladder.target && !(ladder.setback > 0.0 && ladder.setback < ladder.ceiling) {
+            eprintln!("Error: --ladder-setback must be greater than zero and below the ceiling.");
+            std::process::exit(1);
+        }
+    }
 
     let duration = matches
         .get_one::<String>("duration")
         .map(|d| d.parse::<f64>().expect("Invalid duration"));
 
+    let curve = matches
+        .get_one::<String>("curve")
+        .and_then(|name| CURVES.iter().find(|(n, _)| n == name))
+        .map(|&(_, curve)| curve)
+        .expect("Invalid curve");
+
+    // A smooth ramp changes on every beat, so it needs no step size.
     let measures = matches
         .get_one::<String>("measures")
-        .map(|m| m.parse::<u32>().expect("Invalid number of measures"));
+        .map(|m| m.parse::<u32>().expect("Invalid number of measures"))
+        .or_else(|| (curve == Curve::Smooth && duration.is_some()).then_some(1));
 
     if duration.is_some() && measures.is_none() || duration.is_none() && measures.is_some() {
         eprintln!("Error: Both --duration and --measures must be provided together.");
         std::process::exit(1);
     }
+    if duration.is_some_and(|duration| duration <= 0.0) {
+        eprintln!("Error: --duration must be greater than zero.");
+        std::process::exit(1);
+    }
+
+    let polyrhythm = matches.get_one::<String>("polyrhythm").map(|p| {
+        p.parse::<Polyrhythm>().unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        })
+    });
+
+    let mut time_signature = matches
+        .get_one::<String>("time-signature")
+        .expect("Invalid time signature")
+        .parse::<TimeSignature>()
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        });
+
+    if let Some(polyrhythm) = polyrhythm {
+        time_signature = TimeSignature::new(polyrhythm.main, 4);
+    }
+
+    let bars = matches.get_one::<String>("bars").map_or_else(
+        || vec![time_signature],
+        |b| {
+            meter::parse_bars(b).unwrap_or_else(|e| {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            })
+        },
+    );
+    let loop_bars = !matches.get_flag("once");
+
+    let polymeter = matches
+        .get_one::<String>("polymeter")
+        .map(|p| p.parse::<u32>().expect("Invalid polymeter"));
+
+    if polymeter == Some(0) {
+        eprintln!("Error: --polymeter must be at least 1 beat.");
+        std::process::exit(1);
+    }
+
+    let subdivision = matches
+        .get_one::<String>("subdivision")
+        .expect("Invalid subdivision")
+        .parse::<u32>()
+        .expect("Invalid subdivision");
+
+    if subdivision == 0 {
+        eprintln!("Error: --subdivision must be at least 1.");
+        std::process::exit(1);
+    }
+
+    let swing = matches
+        .get_one::<String>("swing")
+        .expect("Invalid swing")
+        .parse::<f64>()
+        .expect("Invalid swing");
+
+    if !(MIN_SWING..=MAX_SWING).contains(&swing) {
+        eprintln!("Error: --swing must be between {MIN_SWING} and {MAX_SWING}.");
+        std::process::exit(1);
+    }
+
+    let volume = matches
+        .get_one::<String>("volume")
+        .expect("Invalid volume")
+        .parse::<f32>()
+        .expect("Invalid volume");
+
+    if !(0.0..=100.0).contains(&volume) {
+        eprintln!("Error: --volume must be between 0 and 100.");
+        std::process::exit(1);
+    }
+
+    let pan = matches.get_one::<String>("pan").map_or_else(Pan::default, |p| {
+        p.parse::<Pan>().unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        })
+    });
+
+    let reference = matches
+        .get_one::<String>("reference")
+        .expect("Invalid reference")
+        .parse::<f64>()
+        .expect("Invalid reference");
+
+    if !(MIN_REFERENCE..=MAX_REFERENCE).contains(&reference) {
+        eprintln!("Error: --reference must be between {MIN_REFERENCE} and {MAX_REFERENCE} Hz.");
+        std::process::exit(1);
+    }
+
+    let drone_level = matches
+        .get_one::<String>("drone-level")
+        .expect("Invalid drone level")
+        .parse::<f32>()
+        .expect("Invalid drone level");
+
+    if !(0.0..=100.0).contains(&drone_level) {
+        eprintln!("Error: --drone-level must be between 0 and 100.");
+        std::process::exit(1);
+    }
 
-    (start_bpm, end_bpm, duration, measures)
+    let note = matches.get_one::<String>("drone").map(|n| {
+        n.parse::<Note>().unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        })
+    });
+    let waveform = matches
+        .get_one::<String>("drone-wave")
+        .and_then(|name| WAVEFORMS.iter().find(|(n, _)| n == name))
+        .map(|&(_, waveform)| waveform)
+        .expect("Invalid drone waveform");
+    let drone = Drone {
+        note: note.unwrap_or(Note::A4),
+        reference,
+        waveform,
+        playing: note.is_some(),
+    };
+
+    let latency = matches.get_one::<String>("latency").map_or_else(
+        || calibration::load_latency().unwrap_or(0.0),
+        |l| l.parse::<f64>().expect("Invalid latency"),
+    );
+
+    if !(0.0..=MAX_LATENCY_MS).contains(&latency) {
+        eprintln!("Error: --latency must be between 0 and {MAX_LATENCY_MS} ms.");
+        std::process::exit(1);
+    }
+
+    let render = matches.get_one::<PathBuf>("render").cloned();
+
+    let sample_rate = matches
+        .get_one::<String>("sample-rate")
+        .map_or(SAMPLE_RATE, |r| r.parse::<u32>().expect("Invalid sample rate"));
+
+    if !(8_000..=192_000).contains(&sample_rate) {
+        eprintln!("Error: --sample-rate must be between 8000 and 192000 Hz.");
+        std::process::exit(1);
+    }
+
+    let length = matches
+        .get_one::<String>("length")
+        .map(|l| l.parse::<f64>().expect("Invalid length"))
+        .or_else(|| {
+            let segments = tempos.len() - 1;
+            #[allow(clippy::cast_precision_loss)]
+            let ramp_length = duration.map(|duration| duration * (segments * cycles as usize) as f64);
+            ramp_length.filter(|_| measures.is_some() && cycles > 0)
+        });
+
+    if length.is_some_and(|length| length <= 0.0) {
+        eprintln!("Error: --length must be greater than zero.");
+        std::process::exit(1);
+    }
+    if render.is_some() && length.is_none() && loop_bars && ladder.is_none() {
+        eprintln!("Error: --render needs a --length unless a progressive ramp with a set number of cycles, a ladder or a --once sequence sets it.");
+        std::process::exit(1);
+    }
+
+    let gap = matches.get_one::<String>("gap").map(|g| {
+        let gap = g.parse::<GapArgs>().unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        });
+        GapArgs {
+            grow: matches
+                .get_one::<String>("gap-grow")
+                .expect("Invalid gap growth")
+                .parse::<u32>()
+                .expect("Invalid gap growth"),
+            ..gap
+        }
+    });
+
+    let displacement = matches
+        .get_one::<String>("displace")
+        .map_or(Displacement::NONE, |d| {
+            d.parse::<Displacement>().unwrap_or_else(|e| {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            })
+        });
+
+    let voice = |name: &str| {
+        if let Some(path) = matches.get_one::<PathBuf>(&format!("{name}-sample")) {
+            return VoiceSpec::sample(path.clone());
+        }
+        matches
+            .get_one::<String>(&format!("{name}-voice"))
+            .expect("Invalid voice")
+            .parse::<VoiceSpec>()
+            .unwrap_or_else(|e| {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            })
+    };
+
+    Args {
+        start_bpm,
+        tempos,
+        cycles,
+        ladder,
+        gap,
+        duration,
+        measures,
+        curve,
+        bars,
+        loop_bars,
+        subdivision,
+        swing,
+        displacement,
+        polyrhythm,
+        polymeter: polymeter.map(|beats| Polymeter { beats }),
+        pattern: matches
+            .get_one::<String>("pattern")
+            .and_then(|name| patterns::index_of(name)),
+        accent_voice: voice("accent"),
+        beat_voice: voice("beat"),
+        subdivision_voice: voice("subdivision"),
+        count: match matches.get_one::<String>("count").map(String::as_str) {
+            Some("beats") => Count::Beats,
+            Some("subdivisions") => Count::Subdivisions,
+            _ => Count::Off,
+        },
+        count_voice: matches.get_one::<PathBuf>("count-voice").cloned(),
+        volume: volume / 100.0,
+        pan,
+        drone,
+        drone_level: drone_level / 100.0,
+        latency,
+        render,
+        sample_rate,
+        length,
+        device: matches.get_one::<String>("device").cloned(),
+        silent: matches.get_flag("silent"),
+    }
 }
@@ -1,13 +1,256 @@
-use rodio::{Decoder, OutputStreamHandle, Sink};
+use rodio::cpal::FromSample;
+use rodio::source::UniformSourceIterator;
+use rodio::{Decoder, Source};
+use std::fs::File;
 use std::io::{BufReader, Cursor};
+use std::path::Path;
+use crate::speech;
+use crate::synth::{self, VoiceKind, VoiceSpec};
 
-pub fn play_tick(stream_handle: &OutputStreamHandle) {
-    let sink = Sink::try_new(stream_handle).unwrap();
+/// Sample rate the live click track is rendered at. Rodio converts it to the device rate.
+pub const SAMPLE_RATE: u32 = 44_100;
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Click {
+    Accent,
+    GroupAccent,
+    Beat,
+    Ghost,
+    Subdivision,
+    /// The second layer of a polyrhythm or polymeter.
+    Layer,
+    /// A word of the spoken count.
+    Count(Syllable),
+}
+
+/// Highest beat number a count recording can replace. Files named after larger numbers
+/// are ignored.
+const MAX_RECORDED_NUMBER: u32 = 64;
+
+/// What the counting voice says: the beat number, or "and", "e" and "a" between beats.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Syllable {
+    Number(u32),
+    And,
+    E,
+    A,
+}
+
+impl Syllable {
+    fn from_word(word: &str) -> Option<Self> {
+        match word {
+            "and" => Some(Self::And),
+            "e" => Some(Self::E),
+            "a" => Some(Self::A),
+            number => number
+                .parse()
+                .ok()
+                .filter(|n| (1..=MAX_RECORDED_NUMBER).contains(n))
+                .map(Self::Number),
+        }
+    }
+}
+
+/// Every click sound, decoded or synthesized once up front and kept as mono samples at
+/// the click track's sample rate.
+pub struct Voices {
+    sample_rate: u32,
+    accent: Vec<f32>,
+    group_accent: Vec<f32>,
+    beat: Vec<f32>,
+    ghost: Vec<f32>,
+    subdivision: Vec<f32>,
+    layer: Vec<f32>,
+    count: CountVoice,
+}
+
+/// Level the count is spoken at. Words land on the beat, so at full level they would clip
+/// on top of the click.
+const COUNT_LEVEL: f32 = 0.4;
+
+/// The spoken words of the count. Numbers without a sample are left silent.
+struct CountVoice {
+    /// Beat numbers from 1 up.
+    numbers: Vec<Vec<f32>>,
+    and: Vec<f32>,
+    e: Vec<f32>,
+    a: Vec<f32>,
+}
+
+impl CountVoice {
+    /// The built-in voice, with any words found in `dir` replacing its own. Every word is
+    /// played at `COUNT_LEVEL`.
+    fn load(dir: Option<&Path>, sample_rate: u32) -> Result<Self, String> {
+        let say = |word: &str| scaled(&speech::say(word, sample_rate), COUNT_LEVEL);
+        let mut count = Self {
+            numbers: (1..=speech::MAX_NUMBER).map(|number| say(&number.to_string())).collect(),
+            and: say("and"),
+            e: say("e"),
+            a: say("a"),
+        };
+        let Some(dir) = dir else {
+            return Ok(count);
+        };
+
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| format!("Unable to read count voice '{}': {e}", dir.display()))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("Unable to read count voice '{}': {e}", dir.display()))?
+                .path();
+            let is_audio = path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| {
+                ["wav", "ogg", "flac"].contains(&ext.to_ascii_lowercase().as_str())
+            });
+            let syllable = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| Syllable::from_word(&stem.to_ascii_lowercase()));
+            if let (true, Some(syllable)) = (is_audio, syllable) {
+                let recording = render(decode_file(&path, "count recording")?, sample_rate);
+                *count.get_mut(syllable) = scaled(&recording, COUNT_LEVEL);
+            }
+        }
+        Ok(count)
+    }
+
+    fn get(&self, syllable: Syllable) -> &[f32] {
+        match syllable {
+            Syllable::Number(number) => self
+                .numbers
+                .get(number as usize - 1)
+                .map_or(&[], Vec::as_slice),
+            Syllable::And => &self.and,
+            Syllable::E => &self.e,
+            Syllable::A => &self.a,
+        }
+    }
+
+    fn get_mut(&mut self, syllable: Syllable) -> &mut Vec<f32> {
+        match syllable {
+            Syllable::Number(number) => {
+                let index = number as usize - 1;
+                if self.numbers.len() <= index {
+                    self.numbers.resize(index + 1, Vec::new());
+                }
+                &mut self.numbers[index]
+            }
+            Syllable::And => &mut self.and,
+            Syllable::E => &mut self.e,
+            Syllable::A => &mut self.a,
+        }
+    }
+}
+
+impl Voices {
+    /// Builds the click sounds from the voices chosen for accents, beats and subdivisions.
+    /// Voices without an explicit level play subdivisions quieter than beats. The count is
+    /// spoken by the built-in voice unless `count_voice` holds replacement recordings.
+    /// Fails if a sample file cannot be read or decoded, or holds no audio.
+    pub fn load(
+        accent: &VoiceSpec,
+        beat: &VoiceSpec,
+        subdivision: &VoiceSpec,
+        count_voice: Option<&Path>,
+        sample_rate: u32,
+    ) -> Result<Self, String> {
+        let accent = accent.with_default_level(1.0);
+        let beat = beat.with_default_level(1.0);
+        let subdivision = subdivision.with_default_level(0.4);
+        // Each recording is decoded once; the pitched layers resample the same one.
+        let accent_recording = recording(&accent, sample_rate)?;
+        let beat_recording = recording(&beat, sample_rate)?;
+        let subdivision_recording = recording(&subdivision, sample_rate)?;
+        let beat_samples = voice(&beat, beat_recording.as_deref(), sample_rate);
+        Ok(Self {
+            sample_rate,
+            accent: voice(&accent, accent_recording.as_deref(), sample_rate),
+            // Group starts in additive meters get a milder lift, a minor third below the accent.
+            group_accent: voice(&accent.pitched(5.0 / 6.0), accent_recording.as_deref(), sample_rate),
+            ghost: scaled(&beat_samples, 0.2),
+            subdivision: voice(&subdivision, subdivision_recording.as_deref(), sample_rate),
+            // The second layer sits a fourth below the main beat.
+            layer: voice(&beat.pitched(0.75), beat_recording.as_deref(), sample_rate),
+            beat: beat_samples,
+            count: CountVoice::load(count_voice, sample_rate)?,
+        })
+    }
+
+    pub const fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn get(&self, click: Click) -> &[f32] {
+        match click {
+            Click::Accent => &self.accent,
+            Click::GroupAccent => &self.group_accent,
+            Click::Beat => &self.beat,
+            Click::Ghost => &self.ghost,
+            Click::Subdivision => &self.subdivision,
+            Click::Layer => &self.layer,
+            Click::Count(syllable) => self.count.get(syllable),
+        }
+    }
+}
+
+/// The recording a sample-based voice plays: its sample file or the bundled tick, as
+/// recorded. `None` for synthesized voices.
+fn recording(spec: &VoiceSpec, sample_rate: u32) -> Result<Option<Vec<f32>>, String> {
+    if spec.kind != VoiceKind::Tick {
+        return Ok(None);
+    }
+    let Some(path) = &spec.sample else {
+        return Ok(Some(render(decode_tick(), sample_rate)));
+    };
+    let samples = render(decode_file(path, "click sample")?, sample_rate);
+    if samples.is_empty() {
+        return Err(format!("Click sample '{}' contains no audio", path.display()));
+    }
+    Ok(Some(samples))
+}
+
+/// Renders `spec`, playing `recording` at the voice's pitch for sample-based voices.
+fn voice(spec: &VoiceSpec, recording: Option<&[f32]>, sample_rate: u32) -> Vec<f32> {
+    let samples = match recording {
+        Some(recording) => {
+            let played = synth::resampled(recording, spec.pitch());
+            match spec.decay {
+                Some(decay) => synth::decayed(&played, decay, sample_rate),
+                None => played,
+            }
+        }
+        None => synth::synthesize(spec.kind, spec.pitch(), spec.decay(), sample_rate),
+    };
+    scaled(&samples, spec.level.unwrap_or(1.0))
+}
+
+/// Opens an audio file, naming it as `what` in errors.
+fn decode_file(path: &Path, what: &str) -> Result<Decoder<BufReader<File>>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Unable to read {what} '{}': {e}", path.display()))?;
+    Decoder::new(BufReader::new(file)).map_err(|e| {
+        format!(
+            "Unsupported {what} '{}': {e}. Use a WAV, OGG or FLAC file",
+            path.display()
+        )
+    })
+}
+
+fn decode_tick() -> Decoder<BufReader<Cursor<&'static [u8]>>> {
     let audio_data = include_bytes!("../assets/audio.ogg");
     let cursor = Cursor::new(&audio_data[..]);
-    let tick = Decoder::new(BufReader::new(cursor)).unwrap();
+    Decoder::new(BufReader::new(cursor)).unwrap()
+}
+
+/// Converts a decoded sound to mono `f32` samples at `sample_rate`.
+fn render<S>(source: S, sample_rate: u32) -> Vec<f32>
+where
+    S: Source,
+    S::Item: rodio::Sample,
+    f32: FromSample<S::Item>,
+{
+    UniformSourceIterator::<S, f32>::new(source, 1, sample_rate).collect()
+}
 
-    sink.append(tick);
-    sink.detach();
+fn scaled(samples: &[f32], gain: f32) -> Vec<f32> {
+    samples.iter().map(|sample| sample * gain).collect()
 }
@@ -0,0 +1,91 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Taps collected before the offset is worked out.
+pub const CALIBRATION_TAPS: usize = 16;
+pub const MAX_LATENCY_MS: f64 = 1000.0;
+
+/// Latency calibration by tapping along with the click. Each tap is compared with the
+/// beat last shown on screen; the median difference is how far the display runs ahead
+/// of what is heard.
+#[derive(Debug, Default)]
+pub struct Calibration {
+    last_beat: Option<Instant>,
+    beat_period: Option<Duration>,
+    /// Tap minus shown beat for every tap so far, in milliseconds.
+    offsets: Vec<f64>,
+}
+
+impl Calibration {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the moment a new beat appears on screen.
+    pub fn on_beat(&mut self, now: Instant) {
+        if let Some(last) = self.last_beat {
+            self.beat_period = Some(now.duration_since(last));
+        }
+        self.last_beat = Some(now);
+    }
+
+    pub fn tap(&mut self, now: Instant) {
+        let (Some(last_beat), Some(period)) = (self.last_beat, self.beat_period) else {
+            return;
+        };
+        let period = period.as_secs_f64() * 1000.0;
+        let mut offset = now.duration_since(last_beat).as_secs_f64() * 1000.0;
+        // A tap in the second half of the beat belongs to the beat that is yet to show.
+        if offset > period / 2.0 {
+            offset -= period;
+        }
+        self.offsets.push(offset);
+    }
+
+    pub const fn taps(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub const fn is_complete(&self) -> bool {
+        self.offsets.len() >= CALIBRATION_TAPS
+    }
+
+    /// The corrected latency, given the latency the display was already delayed by.
+    pub fn latency(&self, current: f64) -> Option<f64> {
+        let mut offsets = self.offsets.clone();
+        if offsets.is_empty() {
+            return None;
+        }
+        offsets.sort_by(f64::total_cmp);
+        let median = offsets[offsets.len() / 2];
+        Some((current + median).clamp(0.0, MAX_LATENCY_MS).round())
+    }
+}
+
+/// The latency saved by the last calibration, if any.
+pub fn load_latency() -> Option<f64> {
+    fs::read_to_string(latency_path()?)
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|latency| (0.0..=MAX_LATENCY_MS).contains(latency))
+}
+
+pub fn save_latency(latency: f64) -> io::Result<()> {
+    let path = latency_path().ok_or_else(|| io::Error::other("no home directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, format!("{latency}\n"))
+}
+
+/// `$XDG_CONFIG_HOME/metronome/latency`, falling back to `~/.config/metronome/latency`.
+fn latency_path() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("metronome").join("latency"))
+}
@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use rodio::Source;
+use crate::audio::{Click, Voices};
+use crate::drone::{Drone, Oscillator};
+use crate::metronome::{Cue, Metronome, ScheduledClick};
+use crate::state::MetronomeState;
+
+/// Frames between reads of the drone setting, about 12 ms at 44.1 kHz.
+const DRONE_REFRESH: u64 = 512;
+
+/// A click that has started and is still sounding.
+struct Sounding {
+    click: Click,
+    position: usize,
+    /// Left and right gain.
+    gain: (f32, f32),
+}
+
+/// The metronome as a stereo audio stream. Every click is copied into the output at the
+/// exact frame its beat position maps to, so timing does not depend on thread wake-ups or
+/// on how the mixer buffers its sources.
+pub struct ClickTrack {
+    metronome: Metronome,
+    voices: Voices,
+    /// Number of frames produced so far.
+    clock: u64,
+    /// Frame at which the next beat starts. Kept fractional so rounding never drifts.
+    next_beat: f64,
+    /// Clicks of the next beat as laid out by the metronome, reused from beat to beat.
+    clicks: Vec<ScheduledClick>,
+    /// Clicks of the current beat that have not started yet with their left and right
+    /// gain, in order.
+    pending: VecDeque<(u64, Click, (f32, f32))>,
+    /// Display updates waiting for their audio to be heard, in order.
+    cues: VecDeque<(u64, Cue)>,
+    /// Frames the display lags the audio by, read from the latency setting every beat.
+    cue_delay: u64,
+    sounding: Vec<Sounding>,
+    /// Drone setting and level, read from the settings every `DRONE_REFRESH` frames.
+    drone: (Drone, f32),
+    oscillator: Oscillator,
+    /// Right channel of the current frame, returned after its left channel.
+    right: Option<f32>,
+}
+
+impl ClickTrack {
+    pub fn new(metronome: Metronome, voices: Voices) -> Self {
+        let drone = metronome.drone();
+        Self {
+            metronome,
+            voices,
+            clock: 0,
+            next_beat: 0.0,
+            clicks: Vec::new(),
+            pending: VecDeque::new(),
+            cues: VecDeque::new(),
+            cue_delay: 0,
+            sounding: Vec::new(),
+            drone,
+            oscillator: Oscillator::default(),
+            right: None,
+        }
+    }
+
+    /// Asks the metronome for the next beat and queues its clicks at frame positions,
+    /// with the gains the mixer had when the beat was laid out. Returns `false` once the
+    /// metronome has nothing left to play.
+    fn schedule_beat(&mut self) -> bool {
+        let Some(beat) = self.metronome.next_beat(&mut self.clicks) else {
+            return false;
+        };
+
+        let rate = f64::from(self.voices.sample_rate());
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        {
+            self.cue_delay = (beat.latency / 1000.0 * rate) as u64;
+        }
+        self.cues
+            .push_back((self.clock + self.cue_delay, Cue::Beat(beat.position)));
+
+        let beat_samples = 60.0 / beat.bpm * beat.length * rate;
+        for scheduled in &self.clicks {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let at = (self.next_beat + scheduled.offset * beat_samples).round() as u64;
+            let gain = beat.mixer.gain(scheduled.click);
+            self.pending.push_back((at, scheduled.click, gain));
+        }
+        self.next_beat += beat_samples;
+        true
+    }
+
+    fn mix(&mut self) -> (f32, f32) {
+        let voices = &self.voices;
+        let (mut left, mut right) = (0.0, 0.0);
+        self.sounding.retain_mut(|sounding| {
+            let samples = voices.get(sounding.click);
+            left += samples[sounding.position] * sounding.gain.0;
+            right += samples[sounding.position] * sounding.gain.1;
+            sounding.position += 1;
+            sounding.position < samples.len()
+        });
+        (left, right)
+    }
+
+    /// Advances the clock by one frame, starting any clicks due on it.
+    fn next_frame(&mut self) -> Option<(f32, f32)> {
+        while let Some(&(at, cue)) = self.cues.front()
+            && at <= self.clock
+        {
+            self.cues.pop_front();
+            self.metronome.show(cue);
+        }
+
+        match self.metronome.state() {
+            MetronomeState::Stopped => return None,
+            MetronomeState::Paused => {
+                // Drop the rest of the interrupted beat and start a fresh one on resume.
+                self.pending.clear();
+                #[allow(clippy::cast_precision_loss)]
+                let now = self.clock as f64;
+                self.next_beat = now;
+            }
+            MetronomeState::Running => {
+                #[allow(clippy::cast_precision_loss)]
+                let now = self.clock as f64;
+                if now >= self.next_beat && !self.schedule_beat() {
+                    return None;
+                }
+                while let Some(&(at, click, gain)) = self.pending.front()
+                    && at <= self.clock
+                {
+                    self.pending.pop_front();
+                    self.cues
+                        .push_back((self.clock + self.cue_delay, Cue::Click(click)));
+                    // A voice pitched out of range renders to nothing.
+                    if self.voices.get(click).is_empty() {
+                        continue;
+                    }
+                    // The voice says one word at a time, so a new word cuts off the last.
+                    if matches!(click, Click::Count(_)) {
+                        self.sounding
+                            .retain(|sounding| !matches!(sounding.click, Click::Count(_)));
+                    }
+                    self.sounding.push(Sounding {
+                        click,
+                        position: 0,
+                        gain,
+                    });
+                }
+            }
+        }
+
+        // The drone sounds whether the metronome runs or is paused.
+        if self.clock.is_multiple_of(DRONE_REFRESH) {
+            self.drone = self.metronome.drone();
+        }
+        let (drone, level) = &self.drone;
+        let drone = self.oscillator.next(drone, *level, self.voices.sample_rate());
+
+        self.clock += 1;
+        let (left, right) = self.mix();
+        Some((left + drone, right + drone))
+    }
+}
+
+impl Iterator for ClickTrack {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if let Some(right) = self.right.take() {
+            return Some(right);
+        }
+        let (left, right) = self.next_frame()?;
+        self.right = Some(right);
+        Some(left)
+    }
+}
+
+impl Source for ClickTrack {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.voices.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,178 @@
+use std::f64::consts::TAU;
+use std::fmt;
+use std::str::FromStr;
+
+/// Frequency of A4 unless another reference is given, in Hz.
+pub const DEFAULT_REFERENCE: f64 = 440.0;
+pub const MIN_REFERENCE: f64 = 400.0;
+pub const MAX_REFERENCE: f64 = 480.0;
+/// Time the drone takes to fade in or out when started or stopped, in seconds.
+const FADE: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Waveform {
+    Sine,
+    Triangle,
+    Square,
+    Sawtooth,
+}
+
+pub const WAVEFORMS: [(&str, Waveform); 4] = [
+    ("sine", Waveform::Sine),
+    ("triangle", Waveform::Triangle),
+    ("square", Waveform::Square),
+    ("sawtooth", Waveform::Sawtooth),
+];
+
+impl Waveform {
+    pub fn name(self) -> &'static str {
+        WAVEFORMS
+            .iter()
+            .find(|(_, waveform)| *waveform == self)
+            .map_or("sine", |(name, _)| name)
+    }
+}
+
+/// A note in scientific pitch notation, such as A4, C#3 or Bb2.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Note {
+    /// Semitones above or below A4.
+    semitones: i32,
+}
+
+impl Note {
+    pub const A4: Self = Self { semitones: 0 };
+
+    /// Equal-tempered frequency of the note with A4 tuned to `reference` Hz.
+    pub fn frequency(self, reference: f64) -> f64 {
+        reference * 2.0_f64.powf(f64::from(self.semitones) / 12.0)
+    }
+}
+
+impl FromStr for Note {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid note '{s}', expected a note and octave such as A4, C#3 or Bb2");
+        let mut chars = s.trim().chars();
+        let letter = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('C') => -9,
+            Some('D') => -7,
+            Some('E') => -5,
+            Some('F') => -4,
+            Some('G') => -2,
+            Some('A') => 0,
+            Some('B') => 2,
+            _ => return Err(invalid()),
+        };
+        let rest = chars.as_str();
+        let (accidental, octave) = match rest.chars().next() {
+            Some('#') => (1, &rest[1..]),
+            Some('b') => (-1, &rest[1..]),
+            _ => (0, rest),
+        };
+        let octave = octave
+            .parse::<i32>()
+            .ok()
+            .filter(|octave| (0..=8).contains(octave))
+            .ok_or_else(invalid)?;
+        Ok(Self {
+            semitones: letter + accidental + 12 * (octave - 4),
+        })
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+        // Count from C0 so the octave number changes at C.
+        let from_c0 = self.semitones + 9 + 12 * 4;
+        write!(
+            f,
+            "{}{}",
+            NAMES[from_c0.rem_euclid(12) as usize],
+            from_c0.div_euclid(12)
+        )
+    }
+}
+
+/// A sustained reference pitch sounding under the click.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drone {
+    pub note: Note,
+    /// Frequency of A4 in Hz.
+    pub reference: f64,
+    pub waveform: Waveform,
+    pub playing: bool,
+}
+
+impl Drone {
+    /// A silent sine A4 at concert pitch, ready to be started.
+    pub const A440: Self = Self {
+        note: Note::A4,
+        reference: DEFAULT_REFERENCE,
+        waveform: Waveform::Sine,
+        playing: false,
+    };
+
+    pub fn frequency(&self) -> f64 {
+        self.note.frequency(self.reference)
+    }
+}
+
+/// Generates the drone sample by sample, fading it in and out so starting, stopping and
+/// level changes never click.
+#[derive(Debug, Default)]
+pub struct Oscillator {
+    /// Position within the current cycle, from 0 to 1.
+    phase: f64,
+    gain: f64,
+}
+
+impl Oscillator {
+    /// The next sample of `drone` played at `level`, or silence once faded out.
+    pub fn next(&mut self, drone: &Drone, level: f32, sample_rate: u32) -> f32 {
+        let rate = f64::from(sample_rate);
+        let target = if drone.playing { f64::from(level) } else { 0.0 };
+        let step = 1.0 / (FADE * rate);
+        self.gain = if self.gain < target {
+            (self.gain + step).min(target)
+        } else {
+            (self.gain - step).max(target)
+        };
+        if self.gain == 0.0 {
+            self.phase = 0.0;
+            return 0.0;
+        }
+
+        let increment = drone.frequency() / rate;
+        let phase = self.phase;
+        self.phase = (self.phase + increment).fract();
+        let sample = match drone.waveform {
+            Waveform::Sine => (TAU * phase).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Square => {
+                let naive = if phase < 0.5 { 1.0 } else { -1.0 };
+                naive + blep(phase, increment) - blep((phase + 0.5).fract(), increment)
+            }
+            Waveform::Sawtooth => 2.0 * phase - 1.0 - blep(phase, increment),
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let sample = (sample * self.gain) as f32;
+        sample
+    }
+}
+
+/// Polynomial correction that rounds off a waveform's jump at phase 0, keeping the
+/// square and sawtooth free of audible aliasing.
+fn blep(phase: f64, increment: f64) -> f64 {
+    if phase < increment {
+        let t = phase / increment;
+        2.0 * t - t * t - 1.0
+    } else if phase > 1.0 - increment {
+        let t = (phase - 1.0) / increment;
+        t * t + 2.0 * t + 1.0
+    } else {
+        0.0
+    }
+}
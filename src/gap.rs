@@ -0,0 +1,86 @@
+use std::str::FromStr;
+
+/// Gap-click trainer settings: `play` bars with the click, then `silent` bars without it,
+/// with the silence growing by `grow` bars after every gap.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct GapArgs {
+    pub play: u32,
+    pub silent: u32,
+    pub grow: u32,
+}
+
+impl FromStr for GapArgs {
+    type Err = String;
+
+    /// Parses `<play>:<silent>`, such as `4:2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid gap '{s}', expected <play bars>:<silent bars>, e.g., 4:2");
+        let (play, silent) = s.split_once(':').ok_or_else(invalid)?;
+        let play = play.trim().parse::<u32>().map_err(|_| invalid())?;
+        let silent = silent.trim().parse::<u32>().map_err(|_| invalid())?;
+        if play == 0 || silent == 0 {
+            return Err(format!("Gap '{s}' needs at least one bar with the click and one without"));
+        }
+        Ok(Self {
+            play,
+            silent,
+            grow: 0,
+        })
+    }
+}
+
+/// Whether the current bar is silent and how many bars of that stretch are left,
+/// counting the current one.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct GapProgress {
+    pub silent: bool,
+    pub bars_left: u32,
+    /// Length of the current or next silent stretch in bars.
+    pub gap: u32,
+}
+
+/// Counts bars to decide which ones the click drops out of. The beats keep being
+/// scheduled through the gap, so the click comes back exactly in phase.
+pub struct Gap {
+    args: GapArgs,
+    progress: GapProgress,
+}
+
+impl Gap {
+    pub const fn new(args: GapArgs) -> Self {
+        Self {
+            progress: GapProgress {
+                silent: false,
+                // Nothing left of the stretch before the first bar.
+                bars_left: 0,
+                gap: args.silent,
+            },
+            args,
+        }
+    }
+
+    /// Moves on to the next bar, switching between clicked and silent stretches.
+    pub const fn next_bar(&mut self) {
+        let progress = &mut self.progress;
+        if progress.bars_left > 1 {
+            progress.bars_left -= 1;
+        } else if progress.silent || progress.bars_left == 0 {
+            if progress.silent {
+                progress.gap += self.args.grow;
+            }
+            progress.silent = false;
+            progress.bars_left = self.args.play;
+        } else {
+            progress.silent = true;
+            progress.bars_left = progress.gap;
+        }
+    }
+
+    pub const fn is_silent(&self) -> bool {
+        self.progress.silent
+    }
+
+    pub const fn progress(&self) -> GapProgress {
+        self.progress
+    }
+}
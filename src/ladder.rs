@@ -0,0 +1,113 @@
+/// Settings of the speed-trainer ladder: climb by `step` BPM every `bars` bars, drop back
+/// by `setback` whenever a climb reaches its peak, and finish once `target` has been played.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LadderArgs {
+    pub start_bpm: f64,
+    pub step: f64,
+    pub bars: u32,
+    pub setback: f64,
+    /// Peak of the first climb. Every later climb peaks `setback` higher.
+    pub ceiling: f64,
+    pub target: f64,
+}
+
+/// Where the ladder is, for the display and the summary at the end of the session.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LadderProgress {
+    pub bpm: f64,
+    /// Tempo the current climb drops back from.
+    pub peak: f64,
+    /// Bar within the current step, from 1 to the bars per step.
+    pub bar: u32,
+    /// Climbs started so far, from 1.
+    pub climb: u32,
+    /// Tempo changes so far, setbacks included.
+    pub steps: u32,
+    pub highest: f64,
+    /// Seconds played so far.
+    pub elapsed: f64,
+    pub finished: bool,
+}
+
+impl LadderProgress {
+    /// A few lines describing the session, printed when the metronome exits.
+    pub fn summary(&self, args: &LadderArgs) -> String {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let seconds = self.elapsed.round() as u64;
+        let outcome = if self.finished {
+            format!("Ladder complete: {} to {} BPM", args.start_bpm, args.target)
+        } else {
+            format!("Ladder stopped at {} BPM of {} BPM", self.bpm, args.target)
+        };
+        format!(
+            "{outcome}\n  Time played: {}:{:02}\n  Climbs: {}, tempo changes: {}, highest tempo: {} BPM",
+            seconds / 60,
+            seconds % 60,
+            self.climb,
+            self.steps,
+            self.highest
+        )
+    }
+}
+
+pub struct Ladder {
+    args: LadderArgs,
+    progress: LadderProgress,
+}
+
+impl Ladder {
+    pub fn new(args: LadderArgs) -> Self {
+        Self {
+            progress: LadderProgress {
+                bpm: args.start_bpm,
+                peak: args.ceiling.min(args.target),
+                bar: 1,
+                climb: 1,
+                steps: 0,
+                highest: args.start_bpm,
+                elapsed: 0.0,
+                finished: false,
+            },
+            args,
+        }
+    }
+
+    pub const fn bpm(&self) -> f64 {
+        self.progress.bpm
+    }
+
+    pub const fn progress(&self) -> LadderProgress {
+        self.progress
+    }
+
+    /// Moves on by a bar, taking the next rung once the bars of the current one are
+    /// played. Returns `false` when the target tempo has been played and the session is over.
+    pub fn next_bar(&mut self) -> bool {
+        let progress = &mut self.progress;
+        if progress.bar < self.args.bars {
+            progress.bar += 1;
+            return true;
+        }
+        if progress.bpm >= self.args.target {
+            progress.finished = true;
+            return false;
+        }
+
+        progress.bar = 1;
+        progress.steps += 1;
+        if progress.bpm >= progress.peak {
+            progress.bpm -= self.args.setback;
+            progress.peak = (progress.peak + self.args.setback).min(self.args.target);
+            progress.climb += 1;
+        } else {
+            progress.bpm = (progress.bpm + self.args.step).min(progress.peak);
+        }
+        progress.highest = progress.highest.max(progress.bpm);
+        true
+    }
+
+    /// Counts a beat of `length` quarter notes played at `bpm` towards the time played.
+    pub fn advance(&mut self, bpm: f64, length: f64) {
+        self.progress.elapsed += 60.0 / bpm * length;
+    }
+}
@@ -1,69 +1,127 @@
 mod args;
 mod audio;
+mod calibration;
+mod click_track;
+mod drone;
+mod gap;
+mod ladder;
+mod meter;
 mod metronome;
+mod output;
+mod patterns;
+mod ramp;
+mod render;
+mod speech;
 mod state;
+mod synth;
 mod tap_tempo;
 mod ui;
 
 use std::sync::{Arc, Mutex};
 use tokio::task::JoinHandle;
-use rodio::OutputStreamHandle;
-use state::{AtomicMetronomeState, MetronomeState};
+use args::Args;
+use audio::Voices;
+use click_track::ClickTrack;
+use meter::TimeSignature;
+use output::Output;
+use state::{AtomicMetronomeState, MetronomeState, Mixer, Position, Settings};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let (start_bpm, end_bpm, duration_opt, measures_opt) = args::parse_arguments();
+    let args = args::parse_arguments();
+    let voices = Voices::load(
+        &args.accent_voice,
+        &args.beat_voice,
+        &args.subdivision_voice,
+        args.count_voice.as_deref(),
+        args.sample_rate,
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    });
 
-    if let Ok((_stream, stream_handle)) = rodio::OutputStream::try_default() {
+    let bpm_shared = Arc::new(Mutex::new(args.start_bpm));
+    let state = Arc::new(AtomicMetronomeState::new(MetronomeState::Running));
+    let settings = Arc::new(Mutex::new(Settings {
+        count: args.count,
+        drone: args.drone,
+        ..Settings::new(
+            args.subdivision,
+            args.swing,
+            args.bars.iter().map(TimeSignature::default_accents).collect(),
+            args.displacement,
+            args.pattern,
+            Mixer::new(args.volume, args.drone_level, args.pan),
+            args.latency,
+        )
+    }));
+    let position = Arc::new(Mutex::new(Position::default()));
 
-        let bpm_shared = Arc::new(Mutex::new(start_bpm));
-        let state = Arc::new(AtomicMetronomeState::new(MetronomeState::Running));
+    let track = click_track(&bpm_shared, &state, &settings, &position, &args, voices);
 
-        let ui_handle = start_ui(&bpm_shared, &state, start_bpm);
-        start_metronome(
-            stream_handle,
-            bpm_shared,
-            state,
-            start_bpm,
-            end_bpm,
-            duration_opt,
-            measures_opt,
-        );
+    if let Some(path) = &args.render {
+        match render::render(path, track, args.length) {
+            Ok(seconds) => println!("Rendered {seconds:.1} s to {}", path.display()),
+            Err(e) => eprintln!("Error: {e}"),
+        }
+        print_ladder_summary(&position, &args);
+        return Ok(());
+    }
 
-        let _ = tokio::join!(ui_handle);
-    } else {
-        eprintln!("Error: Unable to access audio output stream.");
+    match output::start(args.device.clone(), args.silent, track) {
+        Ok(output) => {
+            let ui_handle = start_ui(&bpm_shared, &state, &settings, &position, output, &args);
+            let _ = tokio::join!(ui_handle);
+            print_ladder_summary(&position, &args);
+        }
+        Err(e) => eprintln!("Error: {e}"),
     }
 
     Ok(())
 }
 
+/// Reports how far the speed-trainer ladder got, once the session is over.
+fn print_ladder_summary(position: &Mutex<Position>, args: &Args) {
+    if let Some(ladder) = &args.ladder
+        && let Some(progress) = position.lock().unwrap().ladder
+    {
+        println!("{}", progress.summary(ladder));
+    }
+}
+
 fn start_ui(
     bpm_shared: &Arc<Mutex<f64>>,
     state: &Arc<AtomicMetronomeState>,
-    start_bpm: f64,
+    settings: &Arc<Mutex<Settings>>,
+    position: &Arc<Mutex<Position>>,
+    output: Output,
+    args: &Args,
 ) -> JoinHandle<Result<(), Box<dyn std::error::Error + Send + Sync>>> {
     tokio::spawn(ui::run(
         Arc::clone(bpm_shared),
         Arc::clone(state),
-        start_bpm,
+        Arc::clone(settings),
+        Arc::clone(position),
+        output,
+        args.clone(),
     ))
 }
 
-fn start_metronome(
-    stream_handle: OutputStreamHandle,
-    bpm_shared: Arc<Mutex<f64>>,
-    state: Arc<AtomicMetronomeState>,
-    start_bpm: f64,
-    end_bpm: f64,
-    duration_opt: Option<f64>,
-    measures_opt: Option<u32>,
-) {
-    std::thread::spawn(move || {
-        if let (Some(duration), Some(measures)) = (duration_opt, measures_opt) {
-            let args = metronome::ProgressiveArgs::new(start_bpm, end_bpm, duration, measures);
-            metronome::run_progressive(&args, &stream_handle, &bpm_shared, &state);
-        }
-        metronome::run_constant(&bpm_shared, &stream_handle, &state);
-    });
+fn click_track(
+    bpm_shared: &Arc<Mutex<f64>>,
+    state: &Arc<AtomicMetronomeState>,
+    settings: &Arc<Mutex<Settings>>,
+    position: &Arc<Mutex<Position>>,
+    args: &Args,
+    voices: Voices,
+) -> ClickTrack {
+    let metronome = metronome::Metronome::new(
+        Arc::clone(bpm_shared),
+        Arc::clone(state),
+        Arc::clone(settings),
+        Arc::clone(position),
+        args,
+    );
+    ClickTrack::new(metronome, voices)
 }
@@ -0,0 +1,289 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TimeSignature {
+    pub beats: u32,
+    pub unit: u32,
+    /// Beat groups of an additive meter such as 3+3+2/8; a single group for simple meters.
+    pub groups: Vec<u32>,
+}
+
+impl TimeSignature {
+    pub fn new(beats: u32, unit: u32) -> Self {
+        Self {
+            beats,
+            unit,
+            groups: vec![beats],
+        }
+    }
+
+    pub fn grouped(groups: Vec<u32>, unit: u32) -> Self {
+        Self {
+            beats: groups.iter().sum(),
+            unit,
+            groups,
+        }
+    }
+
+    /// Length of one beat in quarter notes, so an eighth-note meter ticks twice as fast
+    /// as a quarter-note meter at the same BPM.
+    pub fn beat_length(&self) -> f64 {
+        4.0 / f64::from(self.unit)
+    }
+
+    /// Accent pattern for one bar: every group start accented, every other beat normal.
+    pub fn default_accents(&self) -> Vec<BeatAccent> {
+        (1..=self.beats)
+            .map(|beat| {
+                if self.is_group_start(beat) {
+                    BeatAccent::Accent
+                } else {
+                    BeatAccent::Normal
+                }
+            })
+            .collect()
+    }
+
+    /// Whether the 1-based `beat` starts a group. Beat one always does.
+    pub fn is_group_start(&self, beat: u32) -> bool {
+        self.groups
+            .iter()
+            .scan(1, |start, &group| {
+                let this = *start;
+                *start += group;
+                Some(this)
+            })
+            .any(|start| start == beat)
+    }
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self::new(4, 4)
+    }
+}
+
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let groups: Vec<String> = self.groups.iter().map(ToString::to_string).collect();
+        write!(f, "{}/{}", groups.join("+"), self.unit)
+    }
+}
+
+impl FromStr for TimeSignature {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (beats, unit) = s
+            .split_once('/')
+            .ok_or_else(|| format!("'{s}' is not of the form <beats>/<unit>, e.g. 7/8 or 2+2+3/8"))?;
+
+        let groups = beats
+            .split('+')
+            .map(|group| group.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("Invalid number of beats in '{s}'"))?;
+        let unit = unit
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid beat unit in '{s}'"))?;
+
+        if groups.contains(&0) {
+            return Err(format!("Every beat group in '{s}' needs at least one beat"));
+        }
+        if !unit.is_power_of_two() || unit > 64 {
+            return Err(format!("Beat unit in '{s}' must be 1, 2, 4, 8, 16, 32 or 64"));
+        }
+
+        Ok(Self::grouped(groups, unit))
+    }
+}
+
+/// Parses a comma separated bar sequence such as `4/4,3/4,5/8,2+2+3/8`.
+pub fn parse_bars(s: &str) -> Result<Vec<TimeSignature>, String> {
+    let bars = s
+        .split(',')
+        .filter(|bar| !bar.trim().is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<TimeSignature>, _>>()?;
+
+    if bars.is_empty() {
+        return Err(format!("'{s}' does not contain any bars"));
+    }
+    Ok(bars)
+}
+
+/// How strongly a single beat of the bar is played.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BeatAccent {
+    Accent,
+    Normal,
+    Ghost,
+    Mute,
+}
+
+impl BeatAccent {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Accent => Self::Normal,
+            Self::Normal => Self::Ghost,
+            Self::Ghost => Self::Mute,
+            Self::Mute => Self::Accent,
+        }
+    }
+}
+
+/// Most pulses either layer of a polyrhythm may have.
+pub const MAX_POLYRHYTHM_PULSES: u32 = 16;
+
+/// Two click layers sharing one bar: `main` beats against `cross` evenly spaced pulses.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Polyrhythm {
+    pub main: u32,
+    pub cross: u32,
+}
+
+impl Polyrhythm {
+    /// Offsets, as fractions of a beat, of the cross pulses that fall inside the
+    /// 0-based `beat` of the bar.
+    pub fn cross_offsets(self, beat: u32) -> impl Iterator<Item = f64> {
+        (0..self.cross)
+            .map(move |pulse| pulse * self.main)
+            .filter(move |&at| at / self.cross == beat)
+            .map(move |at| f64::from(at % self.cross) / f64::from(self.cross))
+    }
+
+    /// Number of grid cells needed to show both layers on a common grid.
+    pub const fn grid_cells(self) -> u32 {
+        lcm(self.main, self.cross)
+    }
+}
+
+impl fmt::Display for Polyrhythm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.main, self.cross)
+    }
+}
+
+impl FromStr for Polyrhythm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (main, cross) = s
+            .split_once(':')
+            .ok_or_else(|| format!("'{s}' is not of the form <main>:<cross>, e.g. 3:2"))?;
+
+        let main = main
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid main layer in '{s}'"))?;
+        let cross = cross
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid cross layer in '{s}'"))?;
+
+        let pulses = 1..=MAX_POLYRHYTHM_PULSES;
+        if !pulses.contains(&main) || !pulses.contains(&cross) {
+            return Err(format!(
+                "Both layers in '{s}' need between 1 and {MAX_POLYRHYTHM_PULSES} pulses"
+            ));
+        }
+
+        Ok(Self { main, cross })
+    }
+}
+
+/// A second click layer on the same pulse as the main meter but with its own bar length.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Polymeter {
+    pub beats: u32,
+}
+
+impl Polymeter {
+    /// Beats until the downbeats of both layers next fall together, given the 1-based
+    /// beat each layer last played. Zero while both are on their downbeat.
+    pub const fn beats_to_alignment(self, main_beats: u32, beat: u32, layer_beat: u32) -> u32 {
+        if beat == 1 && layer_beat == 1 {
+            return 0;
+        }
+        let cycle = lcm(main_beats, self.beats);
+        let mut remaining = 1;
+        while remaining < cycle {
+            if (beat - 1 + remaining).is_multiple_of(main_beats)
+                && (layer_beat - 1 + remaining).is_multiple_of(self.beats)
+            {
+                break;
+            }
+            remaining += 1;
+        }
+        remaining
+    }
+}
+
+/// Phase offset applied to every click, as a fraction of a beat. Displacing by 1/2 puts
+/// the click on the "and"s while the counter keeps following the real beat.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Displacement {
+    pub steps: u32,
+    pub grid: u32,
+}
+
+impl Displacement {
+    pub const NONE: Self = Self { steps: 0, grid: 1 };
+
+    pub fn offset(self) -> f64 {
+        f64::from(self.steps) / f64::from(self.grid)
+    }
+
+    pub const fn is_none(self) -> bool {
+        self.steps == 0
+    }
+
+    /// Moves one cell later on a grid of `grid` cells per beat, wrapping back to no
+    /// displacement after the last cell.
+    pub const fn next(self, grid: u32) -> Self {
+        let cell = self.steps * grid / self.grid + 1;
+        if cell >= grid { Self::NONE } else { Self { steps: cell, grid } }
+    }
+}
+
+impl fmt::Display for Displacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let divisor = gcd(self.steps, self.grid).max(1);
+        write!(f, "+{}/{}", self.steps / divisor, self.grid / divisor)
+    }
+}
+
+impl FromStr for Displacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (steps, grid) = s
+            .split_once('/')
+            .ok_or_else(|| format!("'{s}' is not a fraction of a beat, e.g. 1/2 or 1/4"))?;
+
+        let steps = steps
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid displacement in '{s}'"))?;
+        let grid = grid
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid displacement in '{s}'"))?;
+
+        if grid == 0 || steps >= grid {
+            return Err(format!("Displacement '{s}' must be less than one beat"));
+        }
+
+        Ok(Self { steps, grid })
+    }
+}
+
+pub const fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+pub const fn lcm(a: u32, b: u32) -> u32 {
+    a / gcd(a, b) * b
+}
@@ -1,121 +1,375 @@
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
-use std::thread::sleep;
-use std::time::{Duration, Instant};
-use rodio::OutputStreamHandle;
-use crate::state::{AtomicMetronomeState, MetronomeState};
-
-pub struct ProgressiveArgs {
-    pub start_bpm: f64,
-    pub end_bpm: f64,
-    pub duration: f64,
-    pub measures: u32,
+use crate::args::Args;
+use crate::audio::{Click, Syllable};
+use crate::drone::Drone;
+use crate::gap::Gap;
+use crate::ladder::Ladder;
+use crate::meter::{BeatAccent, Polymeter, Polyrhythm, TimeSignature};
+use crate::patterns::PATTERNS;
+use crate::ramp::{ProgressiveArgs, Ramp};
+use crate::state::{
+    AtomicMetronomeState, BeatSettings, Count, MetronomeState, Mixer, Position, Settings,
+};
+
+/// Decides what every beat plays: the tempo, the meter and the clicks inside the beat.
+/// The audio stream asks for one beat at a time and places the clicks itself.
+pub struct Metronome {
+    bpm_shared: Arc<Mutex<f64>>,
+    state: Arc<AtomicMetronomeState>,
+    settings: Arc<Mutex<Settings>>,
+    /// Position of the beat being scheduled, ahead of what can be heard.
+    position: Position,
+    /// Position shown to the UI, moved on by cues once the audio is heard.
+    shown: Arc<Mutex<Position>>,
+    ramp: Option<Ramp>,
+    ladder: Option<Ladder>,
+    gap: Option<Gap>,
+    bars: Vec<TimeSignature>,
+    loop_bars: bool,
+    polyrhythm: Option<Polyrhythm>,
+    polymeter: Option<Polymeter>,
+    /// Accent pattern of the bar being played, refreshed from the settings on every downbeat.
+    bar_accents: Vec<BeatAccent>,
+    /// Rhythm pattern of the bar being played, refreshed alongside `bar_accents`.
+    bar_pattern: Option<usize>,
+    /// Clicks displaced past the end of their beat, due in the next one.
+    carried: Vec<ScheduledClick>,
+}
+
+/// A click due `offset` into its beat, as a fraction of the beat's length.
+pub struct ScheduledClick {
+    pub offset: f64,
+    pub click: Click,
+}
+
+/// One beat of the bar with its tempo, its position, its length in quarter notes, and the
+/// mixer and latency its clicks are played with.
+pub struct ScheduledBeat {
+    pub bpm: f64,
+    pub length: f64,
+    pub position: Position,
+    pub mixer: Mixer,
+    pub latency: f64,
+}
+
+/// Something the display should show once the audio it belongs to is heard.
+#[derive(Debug, Clone, Copy)]
+pub enum Cue {
+    Beat(Position),
+    Click(Click),
 }
 
-impl ProgressiveArgs {
-    pub const fn new(start_bpm: f64, end_bpm: f64, duration: f64, measures: u32) -> Self {
+impl Metronome {
+    pub fn new(
+        bpm_shared: Arc<Mutex<f64>>,
+        state: Arc<AtomicMetronomeState>,
+        settings: Arc<Mutex<Settings>>,
+        shown: Arc<Mutex<Position>>,
+        args: &Args,
+    ) -> Self {
+        let ramp = args.duration.zip(args.measures).map(|(duration, measures)| {
+            Ramp::new(ProgressiveArgs::new(
+                args.tempos.clone(),
+                duration,
+                measures,
+                args.curve,
+                args.cycles,
+            ))
+        });
+
         Self {
-            start_bpm,
-            end_bpm,
-            duration,
-            measures,
+            bpm_shared,
+            state,
+            settings,
+            position: Position::default(),
+            shown,
+            ramp,
+            ladder: args.ladder.map(Ladder::new),
+            gap: args.gap.map(Gap::new),
+            bars: args.bars.clone(),
+            loop_bars: args.loop_bars,
+            polyrhythm: args.polyrhythm,
+            polymeter: args.polymeter,
+            bar_accents: args.bars[0].default_accents(),
+            bar_pattern: args.pattern,
+            carried: Vec::new(),
         }
     }
-}
 
-pub fn run_progressive(
-    args: &ProgressiveArgs,
-    stream_handle: &OutputStreamHandle,
-    bpm_shared: &Arc<Mutex<f64>>,
-    state: &AtomicMetronomeState,
-) {
-    let average_bpm = f64::midpoint(args.start_bpm, args.end_bpm);
-    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-    let total_beats = (average_bpm * (args.duration / 60.0)).round() as u32;
+    pub fn state(&self) -> MetronomeState {
+        self.state.load(Ordering::SeqCst)
+    }
 
-    let num_increments = total_beats / args.measures;
-    let bpm_increment = if num_increments > 0 {
-        (args.end_bpm - args.start_bpm) / f64::from(num_increments)
-    } else {
-        0.0
-    };
+    /// Tempo of the next beat in quarter-note BPM. The speed-trainer ladder sets the
+    /// tempo while it runs, and a progressive ramp follows its curve; otherwise, and once
+    /// the ramp is over, the live BPM is used.
+    fn next_tempo(&mut self) -> f64 {
+        if let Some(ladder) = &self.ladder {
+            *self.bpm_shared.lock().unwrap() = ladder.bpm();
+            return ladder.bpm();
+        }
+        if let Some(ramp) = &mut self.ramp {
+            if let Some(bpm) = ramp.next_tempo() {
+                *self.bpm_shared.lock().unwrap() = bpm;
+                return bpm;
+            }
 
-    let mut current_bpm = args.start_bpm;
-    let mut next_beat = Instant::now();
+            *self.bpm_shared.lock().unwrap() = ramp.end_bpm();
+            self.ramp = None;
+        }
+        *self.bpm_shared.lock().unwrap()
+    }
+
+    /// The drone setting and the level the mixer wants it played at.
+    pub fn drone(&self) -> (Drone, f32) {
+        let settings = self.settings.lock().unwrap();
+        (settings.drone, settings.mixer.drone_gain())
+    }
 
-    for beat in 0..total_beats {
-        let current_state = state.load(Ordering::SeqCst);
-        if current_state == MetronomeState::Stopped {
-            break;
+    /// Moves the position shown to the UI on. Called by the audio stream when a cue's
+    /// audio is heard, taking the latency offset into account.
+    pub fn show(&self, cue: Cue) {
+        let mut shown = self.shown.lock().unwrap();
+        match cue {
+            Cue::Beat(position) => {
+                // Cross pulses are counted as they sound, so only a new bar resets them.
+                let cross_pulse = if position.beat == 1 { 0 } else { shown.cross_pulse };
+                *shown = Position {
+                    cross_pulse,
+                    ..position
+                };
+            }
+            Cue::Click(Click::Layer) if self.polyrhythm.is_some() => shown.cross_pulse += 1,
+            Cue::Click(_) => {}
         }
+    }
 
-        if current_state == MetronomeState::Running {
-            super::audio::play_tick(stream_handle);
+    /// Advances the bar/beat counter and lays out every click of the new beat into
+    /// `clicks`, which is cleared first. Clicks displaced past the end of the beat are
+    /// held back and laid out at the start of the next one. Returns `None` and stops the
+    /// metronome once a one-shot bar sequence or the speed-trainer ladder has finished.
+    ///
+    /// The subdivision, swing and displacement are read once per beat, so changing them
+    /// live only takes effect on the following beat and never shifts the beat grid. The
+    /// accent pattern and rhythm pattern are only picked up on the downbeat so a bar never
+    /// plays half old and half new.
+    pub fn next_beat(&mut self, clicks: &mut Vec<ScheduledClick>) -> Option<ScheduledBeat> {
+        // Held by its own handle so the lock can stay taken while the position moves on.
+        let shared = Arc::clone(&self.settings);
+        let (settings, position) = {
+            let shared = shared.lock().unwrap();
+            (shared.beat_settings(), self.advance_position(&shared))
+        };
+        let mut position = position?;
+        if let Some(ladder) = &mut self.ladder
+            && position.is_downbeat()
+            && position.bar > 1
+            && !ladder.next_bar()
+        {
+            // Nothing more will be heard, so show the finished ladder straight away.
+            self.shown.lock().unwrap().ladder = Some(ladder.progress());
+            self.state.store(MetronomeState::Stopped, Ordering::SeqCst);
+            return None;
         }
 
-        while state.load(Ordering::SeqCst) == MetronomeState::Paused {
-            sleep(Duration::from_millis(100));
-            if state.load(Ordering::SeqCst) == MetronomeState::Stopped {
-                return;
+        let time_signature = self.time_signature(position);
+        let bpm = self.next_tempo();
+        if let Some(ramp) = &mut self.ramp {
+            ramp.advance(bpm, time_signature.beat_length());
+        }
+        if let Some(ladder) = &mut self.ladder {
+            ladder.advance(bpm, time_signature.beat_length());
+        }
+        position.ramp = self.ramp.as_ref().map(Ramp::progress);
+        position.ladder = self.ladder.as_ref().map(Ladder::progress);
+        if let Some(gap) = &mut self.gap {
+            if position.is_downbeat() {
+                gap.next_bar();
             }
+            position.gap = Some(gap.progress());
         }
 
-        let beat_duration = 60.0 / current_bpm;
-        next_beat += Duration::from_secs_f64(beat_duration);
-        let now = Instant::now();
+        clicks.clear();
+        match self.bar_pattern {
+            Some(index) => clicks.extend(PATTERNS[index].hits_in_beat(position.beat - 1).map(
+                |(offset, accented)| ScheduledClick {
+                    offset,
+                    click: if accented { Click::Accent } else { Click::Beat },
+                },
+            )),
+            None => self.pulse_clicks(position, &settings, clicks),
+        }
 
-        if next_beat > now {
-            sleep(next_beat - now);
-        } else {
-            next_beat = now;
+        if let Some(polyrhythm) = self.polyrhythm {
+            clicks.extend(polyrhythm.cross_offsets(position.beat - 1).map(|offset| ScheduledClick {
+                offset,
+                click: Click::Layer,
+            }));
         }
 
-        if (beat + 1) % args.measures == 0 && (beat + 1) < total_beats {
-            current_bpm += bpm_increment;
-            {
-                let mut bpm = bpm_shared.lock().unwrap();
-                *bpm = current_bpm;
+        // The polymeter layer shares the pulse, so it only marks its own downbeats.
+        if self.polymeter.is_some() && position.layer_beat == 1 {
+            clicks.push(ScheduledClick {
+                offset: 0.0,
+                click: Click::Layer,
+            });
+        }
+
+        // A silent bar of the gap trainer still takes its place on the beat grid.
+        let silent = self.gap.as_ref().is_some_and(Gap::is_silent);
+        if silent {
+            clicks.clear();
+        }
+
+        displace(clicks, &mut self.carried, settings.displacement.offset());
+
+        // The count is spoken on the beat grid itself, so it keeps time under displaced clicks.
+        if !silent {
+            Self::count_clicks(position, &settings, clicks);
+        }
+
+        clicks.sort_unstable_by(|a, b| a.offset.total_cmp(&b.offset));
+        Some(ScheduledBeat {
+            bpm,
+            length: time_signature.beat_length(),
+            position,
+            mixer: settings.mixer,
+            latency: settings.latency,
+        })
+    }
+
+    /// Moves the position on by one beat, switching to the next bar of the sequence and
+    /// picking up pattern changes on each downbeat.
+    fn advance_position(&mut self, settings: &Settings) -> Option<Position> {
+        let beats_per_bar = self.time_signature(self.position).beats;
+        let position = &mut self.position;
+        position.advance(beats_per_bar);
+
+        if position.is_downbeat() {
+            if position.bar > 1 && self.bar_pattern.is_none() {
+                if position.sequence_bar + 1 < self.bars.len() {
+                    position.sequence_bar += 1;
+                } else if self.loop_bars {
+                    position.sequence_bar = 0;
+                } else {
+                    self.state.store(MetronomeState::Stopped, Ordering::SeqCst);
+                    return None;
+                }
             }
+            self.bar_pattern = settings.pattern;
+            position.pattern = settings.pattern;
+            self.bar_accents.clone_from(&settings.accents[position.sequence_bar]);
+        }
+
+        if let Some(polymeter) = self.polymeter {
+            position.advance_layer(polymeter.beats);
         }
+        Some(*position)
     }
 
-    {
-        let mut bpm = bpm_shared.lock().unwrap();
-        *bpm = args.end_bpm;
+    /// The plain pulse: the beat with its accent followed by its subdivisions.
+    fn pulse_clicks(
+        &self,
+        position: Position,
+        settings: &BeatSettings,
+        clicks: &mut Vec<ScheduledClick>,
+    ) {
+        let accent = self
+            .bar_accents
+            .get(position.beat as usize - 1)
+            .copied()
+            .unwrap_or(BeatAccent::Normal);
+        let beat_click = match accent {
+            BeatAccent::Accent if position.is_downbeat() => Some(Click::Accent),
+            BeatAccent::Accent => Some(Click::GroupAccent),
+            BeatAccent::Normal => Some(Click::Beat),
+            BeatAccent::Ghost => Some(Click::Ghost),
+            BeatAccent::Mute => None,
+        };
+
+        clicks.extend(beat_click.map(|click| ScheduledClick { offset: 0.0, click }));
+        clicks.extend((1..settings.subdivision).map(|i| ScheduledClick {
+            offset: settings.subdivision_offset(i),
+            click: Click::Subdivision,
+        }));
     }
-}
 
-pub fn run_constant(
-    bpm_shared: &Arc<Mutex<f64>>,
-    stream_handle: &OutputStreamHandle,
-    state: &AtomicMetronomeState,
-) {
-    let mut next_beat = Instant::now();
-
-    while state.load(Ordering::SeqCst) != MetronomeState::Stopped {
-        let current_bpm = {
-            let bpm = bpm_shared.lock().unwrap();
-            *bpm
+    /// The spoken count of the beat: its number, then "e", "and" and "a" on the
+    /// subdivisions when they are counted. Only eighths, triplets and sixteenths have words.
+    fn count_clicks(
+        position: Position,
+        settings: &BeatSettings,
+        clicks: &mut Vec<ScheduledClick>,
+    ) {
+        let syllables: &[Syllable] = match (settings.count, settings.subdivision) {
+            (Count::Off, _) => return,
+            (Count::Subdivisions, 2) => &[Syllable::And],
+            (Count::Subdivisions, 3) => &[Syllable::And, Syllable::A],
+            (Count::Subdivisions, 4) => &[Syllable::E, Syllable::And, Syllable::A],
+            _ => &[],
         };
+        let number = ScheduledClick {
+            offset: 0.0,
+            click: Click::Count(Syllable::Number(position.beat)),
+        };
+        clicks.extend(std::iter::once(number).chain((1..).zip(syllables).map(
+            |(i, &syllable)| ScheduledClick {
+                offset: settings.subdivision_offset(i),
+                click: Click::Count(syllable),
+            },
+        )));
+    }
 
-        let current_state = state.load(Ordering::SeqCst);
-        if current_state == MetronomeState::Running {
-            super::audio::play_tick(stream_handle);
+    /// Meter of the bar at `position`: the active rhythm pattern's own meter, otherwise
+    /// the current bar of the sequence.
+    fn time_signature(&self, position: Position) -> TimeSignature {
+        self.bar_pattern.map_or_else(
+            || self.bars[position.sequence_bar].clone(),
+            |index| PATTERNS[index].time_signature(),
+        )
+    }
+}
+
+/// Shifts the clicks of a beat later by `displacement` of a beat. Clicks pushed past the
+/// end of the beat move to `carried` for the next beat, and the clicks carried over from
+/// the previous beat join this one, so every layer keeps its spacing across beats.
+fn displace(clicks: &mut Vec<ScheduledClick>, carried: &mut Vec<ScheduledClick>, displacement: f64) {
+    for scheduled in clicks.iter_mut() {
+        scheduled.offset += displacement;
+    }
+    clicks.append(carried);
+    clicks.retain(|scheduled| {
+        if scheduled.offset < 1.0 {
+            return true;
         }
+        carried.push(ScheduledClick {
+            offset: scheduled.offset - 1.0,
+            click: scheduled.click,
+        });
+        false
+    });
+}
 
-        if current_state == MetronomeState::Running {
-            let beat_duration = 60.0 / current_bpm;
-            next_beat += Duration::from_secs_f64(beat_duration);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            let now = Instant::now();
-            if next_beat > now {
-                sleep(next_beat - now);
-            } else {
-                next_beat = now;
-            }
-        } else if current_state == MetronomeState::Paused {
-            sleep(Duration::from_millis(100));
-            next_beat = Instant::now();
+    #[test]
+    fn displaced_polyrhythm_keeps_its_spacing() {
+        let polyrhythm = Polyrhythm { main: 3, cross: 2 };
+        let (mut clicks, mut carried) = (Vec::new(), Vec::new());
+        let mut times = Vec::new();
+        for beat in 0..6 {
+            clicks.clear();
+            clicks.extend(polyrhythm.cross_offsets(beat % 3).map(|offset| ScheduledClick {
+                offset,
+                click: Click::Layer,
+            }));
+            displace(&mut clicks, &mut carried, 0.5);
+            times.extend(clicks.iter().map(|scheduled| f64::from(beat) + scheduled.offset));
         }
+        // Two evenly spaced pulses a bar, half a beat late: the second lands on beat 3.
+        assert_eq!(times, [0.5, 2.0, 3.5, 5.0]);
     }
 }
@@ -0,0 +1,264 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::{OutputStream, OutputStreamHandle, Source};
+use crate::click_track::ClickTrack;
+
+/// Samples taken from the click track each time the stream locks it.
+const CHUNK: usize = 512;
+
+/// The device playing the click track, and why the last switch failed if it did.
+#[derive(Debug, Clone, Default)]
+pub struct OutputStatus {
+    pub device: String,
+    /// Running without audio, either on request or for lack of a device.
+    pub silent: bool,
+    pub error: Option<String>,
+}
+
+impl OutputStatus {
+    fn silent(error: Option<String>) -> Self {
+        Self {
+            device: String::new(),
+            silent: true,
+            error,
+        }
+    }
+}
+
+/// Handle to the output thread. The stream lives on its own thread because audio
+/// streams cannot move between threads, so device switches are sent to it by name.
+#[derive(Clone)]
+pub struct Output {
+    requests: Sender<String>,
+    status: Arc<Mutex<OutputStatus>>,
+}
+
+impl Output {
+    pub fn switch(&self, device: String) {
+        let _ = self.requests.send(device);
+    }
+
+    pub fn status(&self) -> OutputStatus {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+/// Names of the output devices of the default audio host.
+pub fn device_names() -> Vec<String> {
+    rodio::cpal::default_host()
+        .output_devices()
+        .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Starts playing `track` on the named device, or the default device if none is given.
+/// When `silent` is set, or there is no default device to play on, the track is clocked in
+/// real time without audio so the beat counter and display keep running.
+pub fn start(device: Option<String>, silent: bool, track: ClickTrack) -> Result<Output, String> {
+    let (requests, receiver) = mpsc::channel::<String>();
+    let (opened_sender, opened) = mpsc::channel();
+    let status = Arc::new(Mutex::new(OutputStatus::default()));
+    let thread_status = Arc::clone(&status);
+
+    thread::spawn(move || {
+        let track = Arc::new(Mutex::new(track));
+        let generation = Arc::new(AtomicUsize::new(0));
+        let mut current = None;
+        let mut clock = SilentClock::new();
+
+        {
+            let mut status = thread_status.lock().unwrap();
+            if silent {
+                *status = OutputStatus::silent(None);
+            } else {
+                match open(device.as_deref()).and_then(|(stream, handle, name)| {
+                    play(&handle, &track, &generation)?;
+                    Ok((stream, name))
+                }) {
+                    Ok((stream, name)) => {
+                        current = Some(stream);
+                        status.device = name;
+                    }
+                    Err(e) if device.is_some() => {
+                        let _ = opened_sender.send(Err(e));
+                        return;
+                    }
+                    Err(_) => {
+                        *status = OutputStatus::silent(Some(
+                            "No audio output available, running silently".to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+        let _ = opened_sender.send(Ok(()));
+
+        loop {
+            let request = if current.is_some() || !clock.running {
+                receiver.recv().map_err(|_| TryRecvError::Disconnected)
+            } else {
+                clock.tick(&track);
+                receiver.try_recv()
+            };
+            let name = match request {
+                Ok(name) => name,
+                Err(TryRecvError::Empty) => continue,
+                Err(TryRecvError::Disconnected) => break,
+            };
+
+            let switched = open(Some(&name)).and_then(|(stream, handle, name)| {
+                // Retire the source on the old stream before the new one starts pulling,
+                // so the click track is never played twice over.
+                generation.fetch_add(1, Ordering::SeqCst);
+                play(&handle, &track, &generation)?;
+                Ok((stream, name))
+            });
+            let mut status = thread_status.lock().unwrap();
+            match switched {
+                Ok((stream, name)) => {
+                    current = Some(stream);
+                    *status = OutputStatus {
+                        device: name,
+                        silent: false,
+                        error: None,
+                    };
+                }
+                Err(e) => status.error = Some(e),
+            }
+        }
+        drop(current);
+    });
+
+    opened
+        .recv()
+        .map_err(|_| "Unable to access audio output stream.".to_string())??;
+    Ok(Output { requests, status })
+}
+
+/// Pulls the click track in real time and throws the audio away, standing in for an
+/// output stream when there is no device to play on.
+struct SilentClock {
+    start: Instant,
+    frames: u64,
+    /// Cleared once the click track has finished.
+    running: bool,
+}
+
+impl SilentClock {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            frames: 0,
+            running: true,
+        }
+    }
+
+    /// Produces one chunk of the click track and sleeps until it would have played.
+    fn tick(&mut self, track: &Mutex<ClickTrack>) {
+        let (produced, channels, rate) = {
+            let mut track = track.lock().unwrap();
+            let channels = track.channels();
+            let rate = track.sample_rate();
+            let produced = track.by_ref().take(CHUNK * usize::from(channels)).count();
+            (produced, channels, rate)
+        };
+        if produced < CHUNK * usize::from(channels) {
+            self.running = false;
+        }
+
+        self.frames += (produced / usize::from(channels)) as u64;
+        #[allow(clippy::cast_precision_loss)]
+        let due = self.start + Duration::from_secs_f64(self.frames as f64 / f64::from(rate));
+        thread::sleep(due.saturating_duration_since(Instant::now()));
+    }
+}
+
+fn open(device: Option<&str>) -> Result<(OutputStream, OutputStreamHandle, String), String> {
+    let Some(name) = device else {
+        let (stream, handle) = OutputStream::try_default()
+            .map_err(|_| "Unable to access audio output stream.".to_string())?;
+        let name = rodio::cpal::default_host()
+            .default_output_device()
+            .and_then(|device| device.name().ok())
+            .unwrap_or_else(|| "default".to_string());
+        return Ok((stream, handle, name));
+    };
+
+    let device = rodio::cpal::default_host()
+        .output_devices()
+        .map_err(|e| format!("Unable to list output devices: {e}"))?
+        .find(|device| device.name().is_ok_and(|device| device == name))
+        .ok_or_else(|| {
+            format!("No output device named '{name}'. Run with --list-devices to see them")
+        })?;
+    let (stream, handle) = OutputStream::try_from_device(&device)
+        .map_err(|e| format!("Unable to open output device '{name}': {e}"))?;
+    Ok((stream, handle, name.to_string()))
+}
+
+fn play(
+    handle: &OutputStreamHandle,
+    track: &Arc<Mutex<ClickTrack>>,
+    generation: &Arc<AtomicUsize>,
+) -> Result<(), String> {
+    let source = SharedTrack {
+        track: Arc::clone(track),
+        generation: Arc::clone(generation),
+        own: generation.load(Ordering::SeqCst),
+        buffer: Vec::with_capacity(CHUNK),
+        position: 0,
+    };
+    handle
+        .play_raw(source)
+        .map_err(|e| format!("Unable to play on output device: {e}"))
+}
+
+/// One stream's view of the click track. It ends as soon as a newer stream takes over.
+struct SharedTrack {
+    track: Arc<Mutex<ClickTrack>>,
+    generation: Arc<AtomicUsize>,
+    own: usize,
+    buffer: Vec<f32>,
+    position: usize,
+}
+
+impl Iterator for SharedTrack {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position == self.buffer.len() {
+            if self.generation.load(Ordering::SeqCst) != self.own {
+                return None;
+            }
+            self.buffer.clear();
+            self.position = 0;
+            self.buffer
+                .extend(self.track.lock().unwrap().by_ref().take(CHUNK));
+        }
+        let sample = self.buffer.get(self.position).copied()?;
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for SharedTrack {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.track.lock().unwrap().channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.track.lock().unwrap().sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,111 @@
+use crate::meter::TimeSignature;
+
+/// A named one-bar click pattern. `hits` holds one character per grid cell:
+/// `X` for an accented hit, `x` for a normal hit and `.` for a rest.
+#[derive(Debug)]
+pub struct Pattern {
+    pub name: &'static str,
+    pub groups: &'static [u32],
+    pub unit: u32,
+    /// Grid cells per beat.
+    pub grid: u32,
+    pub hits: &'static str,
+}
+
+pub const PATTERNS: &[Pattern] = &[
+    Pattern {
+        name: "son-clave-3-2",
+        groups: &[4],
+        unit: 4,
+        grid: 4,
+        hits: "X..x..x...x.x...",
+    },
+    Pattern {
+        name: "son-clave-2-3",
+        groups: &[4],
+        unit: 4,
+        grid: 4,
+        hits: "..x.x...X..x..x.",
+    },
+    Pattern {
+        name: "rumba-clave-3-2",
+        groups: &[4],
+        unit: 4,
+        grid: 4,
+        hits: "X..x...x..x.x...",
+    },
+    Pattern {
+        name: "rumba-clave-2-3",
+        groups: &[4],
+        unit: 4,
+        grid: 4,
+        hits: "..x.x...X..x...x",
+    },
+    Pattern {
+        name: "bossa-nova",
+        groups: &[4],
+        unit: 4,
+        grid: 4,
+        hits: "X..x..x...x..x..",
+    },
+    Pattern {
+        name: "tresillo",
+        groups: &[4],
+        unit: 4,
+        grid: 2,
+        hits: "X..x..x.",
+    },
+    Pattern {
+        name: "cascara",
+        groups: &[4],
+        unit: 4,
+        grid: 4,
+        hits: "X.x.xx.x.x.xx.x.",
+    },
+    Pattern {
+        name: "bell-12-8",
+        groups: &[3, 3, 3, 3],
+        unit: 8,
+        grid: 1,
+        hits: "X.x.xx.x.x.x",
+    },
+    Pattern {
+        name: "fume-fume-12-8",
+        groups: &[3, 3, 3, 3],
+        unit: 8,
+        grid: 1,
+        hits: "X.x.x..x.x..",
+    },
+];
+
+impl Pattern {
+    pub fn time_signature(&self) -> TimeSignature {
+        TimeSignature::grouped(self.groups.to_vec(), self.unit)
+    }
+
+    /// Hits inside the 0-based `beat` as `(offset, accented)`, with the offset given as
+    /// a fraction of the beat.
+    pub fn hits_in_beat(&self, beat: u32) -> impl Iterator<Item = (f64, bool)> + '_ {
+        let grid = self.grid as usize;
+        let start = beat as usize * grid;
+        self.hits
+            .bytes()
+            .skip(start)
+            .take(grid)
+            .enumerate()
+            .filter(|&(_, hit)| hit != b'.')
+            .map(move |(cell, hit)| {
+                #[allow(clippy::cast_precision_loss)]
+                let offset = cell as f64 / grid as f64;
+                (offset, hit == b'X')
+            })
+    }
+}
+
+pub fn names() -> impl Iterator<Item = &'static str> {
+    PATTERNS.iter().map(|pattern| pattern.name)
+}
+
+pub fn index_of(name: &str) -> Option<usize> {
+    PATTERNS.iter().position(|pattern| pattern.name == name)
+}
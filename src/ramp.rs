@@ -0,0 +1,141 @@
+/// How the tempo travels from the start to the end of a progressive ramp.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Curve {
+    /// Equal BPM gained every second.
+    Linear,
+    /// Equal percentage gained every second, so slow tempos move in smaller steps.
+    Exponential,
+    /// Large changes early that settle gently into the target.
+    Logarithmic,
+    /// Eases out of the start tempo and into the target.
+    Sigmoid,
+    /// Linear, but changing on every beat instead of every `measures` beats.
+    Smooth,
+}
+
+pub const CURVES: [(&str, Curve); 5] = [
+    ("linear", Curve::Linear),
+    ("exponential", Curve::Exponential),
+    ("logarithmic", Curve::Logarithmic),
+    ("s-curve", Curve::Sigmoid),
+    ("smooth", Curve::Smooth),
+];
+
+impl Curve {
+    /// Tempo `progress` of the way through a ramp from `start` to `end` BPM, where
+    /// `progress` runs from 0 to 1.
+    fn tempo(self, start: f64, end: f64, progress: f64) -> f64 {
+        let x = progress.clamp(0.0, 1.0);
+        match self {
+            Self::Linear | Self::Smooth => start + (end - start) * x,
+            Self::Exponential => start * (end / start).powf(x),
+            // The exponential curve turned upside down and played backwards.
+            Self::Logarithmic => start + end - start * (end / start).powf(1.0 - x),
+            Self::Sigmoid => start + (end - start) * x * x * (3.0 - 2.0 * x),
+        }
+    }
+}
+
+/// A tempo ramp through any number of tempos, each segment taking `duration` seconds.
+pub struct ProgressiveArgs {
+    /// Tempos the ramp passes through in order, at least two. Neighbouring tempos form
+    /// a segment, so 60, 120, 60 goes up and back down.
+    pub tempos: Vec<f64>,
+    pub duration: f64,
+    pub measures: u32,
+    pub curve: Curve,
+    /// Times the whole ramp is played; 0 repeats it until the metronome is stopped.
+    pub cycles: u32,
+}
+
+impl ProgressiveArgs {
+    pub const fn new(tempos: Vec<f64>, duration: f64, measures: u32, curve: Curve, cycles: u32) -> Self {
+        Self {
+            tempos,
+            duration,
+            measures,
+            curve,
+            cycles,
+        }
+    }
+
+    pub const fn segments(&self) -> usize {
+        self.tempos.len() - 1
+    }
+}
+
+/// Which segment of which cycle a ramp is in, both counted from 0.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct RampProgress {
+    pub segment: usize,
+    pub cycle: u32,
+}
+
+/// A progressive tempo change in progress. The curve is followed against the time
+/// actually played, so each segment reaches its target on the first beat at `duration`
+/// whatever the beat unit or curve.
+pub struct Ramp {
+    args: ProgressiveArgs,
+    /// Beats between tempo changes.
+    step: u32,
+    progress: RampProgress,
+    /// Beats played in the current segment.
+    beat: u32,
+    /// Seconds played in the current segment.
+    elapsed: f64,
+    current_bpm: f64,
+}
+
+impl Ramp {
+    pub fn new(args: ProgressiveArgs) -> Self {
+        let step = if args.curve == Curve::Smooth { 1 } else { args.measures.max(1) };
+        Self {
+            current_bpm: args.tempos[0],
+            args,
+            step,
+            progress: RampProgress::default(),
+            beat: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Tempo of the next beat, moving along the curve every `step` beats and on to the next
+    /// segment once a segment has run for its duration. `None` once every cycle is done.
+    pub fn next_tempo(&mut self) -> Option<f64> {
+        while self.elapsed >= self.args.duration {
+            // Carry the overshoot of the last beat so repeated cycles never drift.
+            self.elapsed -= self.args.duration;
+            self.beat = 0;
+            self.progress.segment += 1;
+            if self.progress.segment == self.args.segments() {
+                self.progress.segment = 0;
+                self.progress.cycle += 1;
+                if self.args.cycles != 0 && self.progress.cycle >= self.args.cycles {
+                    return None;
+                }
+            }
+        }
+        if self.beat.is_multiple_of(self.step) {
+            let segment = self.progress.segment;
+            let (start, end) = (self.args.tempos[segment], self.args.tempos[segment + 1]);
+            let progress = self.elapsed / self.args.duration;
+            self.current_bpm = self.args.curve.tempo(start, end, progress);
+        }
+        self.beat += 1;
+        Some(self.current_bpm)
+    }
+
+    /// Counts a beat of `length` quarter notes played at `bpm` towards the duration.
+    pub fn advance(&mut self, bpm: f64, length: f64) {
+        self.elapsed += 60.0 / bpm * length;
+    }
+
+    pub const fn progress(&self) -> RampProgress {
+        self.progress
+    }
+
+    /// The tempo the ramp finishes on.
+    pub fn end_bpm(&self) -> f64 {
+        self.args.tempos[self.args.segments()]
+    }
+}
@@ -0,0 +1,40 @@
+use std::path::Path;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rodio::Source;
+use crate::click_track::ClickTrack;
+
+/// Writes the click track to a 16-bit PCM WAV file as fast as it can be computed.
+/// Stops after `length` seconds, or when the metronome finishes if no length is given.
+/// Returns the number of seconds written.
+pub fn render(path: &Path, track: ClickTrack, length: Option<f64>) -> Result<f64, String> {
+    let spec = WavSpec {
+        channels: track.channels(),
+        sample_rate: track.sample_rate(),
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let mut writer = WavWriter::create(path, spec)
+        .map_err(|e| format!("Unable to create '{}': {e}", path.display()))?;
+
+    let rate = f64::from(spec.sample_rate);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let frames = length.map_or(usize::MAX, |seconds| (seconds * rate).round() as usize);
+    let samples = frames.saturating_mul(usize::from(spec.channels));
+
+    let mut written: u64 = 0;
+    for sample in track.take(samples) {
+        #[allow(clippy::cast_possible_truncation)]
+        let sample = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+        writer
+            .write_sample(sample)
+            .map_err(|e| format!("Unable to write '{}': {e}", path.display()))?;
+        written += 1;
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("Unable to write '{}': {e}", path.display()))?;
+
+    #[allow(clippy::cast_precision_loss)]
+    let seconds = written as f64 / f64::from(spec.channels) / rate;
+    Ok(seconds)
+}
@@ -0,0 +1,191 @@
+use std::f64::consts::{PI, TAU};
+
+/// A sound of the built-in counting voice: formant frequencies in Hz, how strongly the
+/// vocal cords and the breath noise sound, and where the noise is centred.
+#[derive(Debug, Clone, Copy)]
+struct Phoneme {
+    formants: [f64; 3],
+    voicing: f64,
+    noise: f64,
+    noise_centre: f64,
+    /// Length in seconds.
+    length: f64,
+}
+
+const fn vowel(f1: f64, f2: f64, f3: f64, length: f64) -> Phoneme {
+    Phoneme {
+        formants: [f1, f2, f3],
+        voicing: 1.0,
+        noise: 0.0,
+        noise_centre: 0.0,
+        length,
+    }
+}
+
+const fn voiced(f1: f64, f2: f64, f3: f64, voicing: f64, length: f64) -> Phoneme {
+    Phoneme {
+        voicing,
+        ..vowel(f1, f2, f3, length)
+    }
+}
+
+const fn fricative(noise: f64, noise_centre: f64, voicing: f64, length: f64) -> Phoneme {
+    Phoneme {
+        formants: [400.0, 1500.0, 2500.0],
+        voicing,
+        noise,
+        noise_centre,
+        length,
+    }
+}
+
+/// The closure of a stop consonant, silent until its burst.
+const fn silence(length: f64) -> Phoneme {
+    Phoneme {
+        voicing: 0.0,
+        ..vowel(400.0, 1500.0, 2500.0, length)
+    }
+}
+
+const AH: Phoneme = vowel(640.0, 1190.0, 2390.0, 0.16);
+const AA: Phoneme = vowel(730.0, 1090.0, 2440.0, 0.12);
+const AE: Phoneme = vowel(660.0, 1720.0, 2410.0, 0.16);
+const AO: Phoneme = vowel(570.0, 840.0, 2410.0, 0.22);
+const EH: Phoneme = vowel(530.0, 1840.0, 2480.0, 0.13);
+const IH: Phoneme = vowel(390.0, 1990.0, 2550.0, 0.1);
+const IY: Phoneme = vowel(270.0, 2290.0, 3010.0, 0.2);
+const UW: Phoneme = vowel(300.0, 870.0, 2240.0, 0.22);
+const SHORT_IY: Phoneme = vowel(300.0, 2200.0, 2950.0, 0.08);
+const W: Phoneme = voiced(290.0, 610.0, 2150.0, 0.7, 0.07);
+const R: Phoneme = voiced(310.0, 1060.0, 1380.0, 0.8, 0.07);
+const L: Phoneme = voiced(360.0, 1000.0, 2700.0, 0.7, 0.06);
+const N: Phoneme = voiced(250.0, 1700.0, 2600.0, 0.45, 0.09);
+const V: Phoneme = Phoneme {
+    noise: 0.15,
+    noise_centre: 3500.0,
+    ..voiced(250.0, 1200.0, 2400.0, 0.4, 0.06)
+};
+const F: Phoneme = fricative(0.35, 5000.0, 0.0, 0.1);
+const S: Phoneme = fricative(0.7, 6500.0, 0.0, 0.12);
+const TH: Phoneme = fricative(0.25, 4500.0, 0.0, 0.09);
+const CLOSURE: Phoneme = silence(0.04);
+const T_BURST: Phoneme = fricative(0.6, 4500.0, 0.0, 0.03);
+const K_BURST: Phoneme = fricative(0.6, 2000.0, 0.0, 0.035);
+const D_BURST: Phoneme = fricative(0.3, 3500.0, 0.3, 0.02);
+
+/// Highest beat number the built-in voice can say.
+pub const MAX_NUMBER: u32 = 12;
+
+/// Phonemes of a word the counting voice says: "one" to "twelve", "and", "e" and "a".
+fn phonemes(word: &str) -> &'static [Phoneme] {
+    match word {
+        "1" => &[W, AH, N],
+        "2" => &[T_BURST, UW],
+        "3" => &[TH, R, IY],
+        "4" => &[F, AO, R],
+        "5" => &[F, AA, SHORT_IY, V],
+        "6" => &[S, IH, CLOSURE, K_BURST, S],
+        "7" => &[S, EH, V, AH, N],
+        "8" => &[EH, SHORT_IY, CLOSURE, T_BURST],
+        "9" => &[N, AA, SHORT_IY, N],
+        "10" => &[T_BURST, EH, N],
+        "11" => &[IH, L, EH, V, AH, N],
+        "12" => &[T_BURST, W, EH, L, V],
+        "and" => &[AE, N, D_BURST],
+        "e" => &[IY],
+        "a" => &[AH],
+        _ => &[],
+    }
+}
+
+/// Speaks a word with the built-in formant voice, as mono samples peaking at 1.0.
+/// Words are the beat numbers "1" to "12", "and", "e" and "a".
+pub fn say(word: &str, sample_rate: u32) -> Vec<f32> {
+    let phonemes = phonemes(word);
+    let rate = f64::from(sample_rate);
+    let total: f64 = phonemes.iter().map(|phoneme| phoneme.length).sum();
+    // Glide between neighbouring sounds instead of jumping.
+    let transition = 0.03;
+
+    let mut resonators = [Resonator::default(); 3];
+    let mut hiss = Resonator::default();
+    let mut noise = 0x2545_F491_u32;
+    let mut phase = 0.0;
+    let mut samples = Vec::new();
+    let mut start = 0.0;
+
+    for (index, phoneme) in phonemes.iter().enumerate() {
+        let previous = index.checked_sub(1).map_or(phoneme, |previous| &phonemes[previous]);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let frames = (phoneme.length * rate) as usize;
+        for frame in 0..frames {
+            #[allow(clippy::cast_precision_loss)]
+            let t = frame as f64 / rate;
+            let blend = (t / transition).min(1.0);
+            let mix = |from: f64, to: f64| from + (to - from) * blend;
+
+            // Pitch falls across the word like a spoken statement.
+            let pitch = 130.0 - 30.0 * (start + t) / total;
+            phase = (phase + pitch / rate).fract();
+            let pulse = 1.0 - 2.0 * phase;
+
+            noise ^= noise << 13;
+            noise ^= noise >> 17;
+            noise ^= noise << 5;
+            let white = f64::from(noise) / f64::from(u32::MAX) * 2.0 - 1.0;
+
+            let mut voice = pulse * mix(previous.voicing, phoneme.voicing);
+            for (resonator, (&from, &to)) in resonators
+                .iter_mut()
+                .zip(previous.formants.iter().zip(phoneme.formants.iter()))
+            {
+                voice = resonator.filter(voice, mix(from, to), 90.0, rate);
+            }
+            let breath = hiss.filter(white, phoneme.noise_centre.max(500.0), 1500.0, rate)
+                * mix(previous.noise, phoneme.noise);
+
+            // Hiss sits well below the vowels, as it does in speech.
+            samples.push(voice + breath * 0.3);
+        }
+        start += phoneme.length;
+    }
+
+    fade(&mut samples, 0.01 * rate);
+    normalize(&samples)
+}
+
+/// Two-pole resonator tuned per sample.
+#[derive(Debug, Clone, Copy, Default)]
+struct Resonator {
+    y1: f64,
+    y2: f64,
+}
+
+impl Resonator {
+    fn filter(&mut self, x: f64, frequency: f64, bandwidth: f64, rate: f64) -> f64 {
+        let c = -(-TAU * bandwidth / rate).exp();
+        let b = 2.0 * (-PI * bandwidth / rate).exp() * (TAU * frequency / rate).cos();
+        let a = 1.0 - b - c;
+        let y = a * x + b * self.y1 + c * self.y2;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+/// Fades both ends of the word in and out over `frames` to avoid clicks.
+fn fade(samples: &mut [f64], frames: f64) {
+    let len = samples.len();
+    for (index, sample) in samples.iter_mut().enumerate() {
+        #[allow(clippy::cast_precision_loss)]
+        let edge = index.min(len - 1 - index) as f64;
+        *sample *= (edge / frames).min(1.0);
+    }
+}
+
+fn normalize(samples: &[f64]) -> Vec<f32> {
+    let peak = samples.iter().fold(0.0_f64, |peak, sample| peak.max(sample.abs()));
+    let gain = if peak > 0.0 { 1.0 / peak } else { 0.0 };
+    #[allow(clippy::cast_possible_truncation)]
+    samples.iter().map(|sample| (sample * gain) as f32).collect()
+}
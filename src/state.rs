@@ -1,4 +1,11 @@
+use std::str::FromStr;
 use std::sync::atomic::{AtomicU8, Ordering};
+use crate::audio::Click;
+use crate::drone::Drone;
+use crate::gap::GapProgress;
+use crate::ladder::LadderProgress;
+use crate::meter::{BeatAccent, Displacement};
+use crate::ramp::RampProgress;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum MetronomeState {
@@ -36,3 +43,374 @@ impl AtomicMetronomeState {
         self.state.store(state as u8, ordering);
     }
 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Position {
+    pub bar: u32,
+    pub beat: u32,
+    /// 1-based pulse of the polyrhythm cross layer within the bar, 0 before its first pulse.
+    pub cross_pulse: u32,
+    /// 1-based beat within the polymeter layer's own bar, 0 before its first beat.
+    pub layer_beat: u32,
+    /// Index of the current bar within the bar sequence.
+    pub sequence_bar: usize,
+    /// Index into `PATTERNS` of the rhythm pattern playing in this bar, if any.
+    pub pattern: Option<usize>,
+    /// Segment and cycle of the progressive ramp, while one is running.
+    pub ramp: Option<RampProgress>,
+    /// Progress of the speed-trainer ladder, if one is running.
+    pub ladder: Option<LadderProgress>,
+    /// Whether the gap trainer has silenced this bar, if it is in use.
+    pub gap: Option<GapProgress>,
+}
+
+impl Position {
+    /// Moves to the next beat, rolling over into a new bar after `beats_per_bar` beats.
+    /// Both counters are 1-based once the first beat has been played.
+    pub const fn advance(&mut self, beats_per_bar: u32) {
+        if self.bar == 0 || self.beat >= beats_per_bar {
+            self.bar += 1;
+            self.beat = 1;
+            self.cross_pulse = 0;
+        } else {
+            self.beat += 1;
+        }
+    }
+
+    /// Moves the polymeter layer on by one beat, wrapping after `layer_beats` beats.
+    pub const fn advance_layer(&mut self, layer_beats: u32) {
+        if self.layer_beat == 0 || self.layer_beat >= layer_beats {
+            self.layer_beat = 1;
+        } else {
+            self.layer_beat += 1;
+        }
+    }
+
+    pub const fn is_downbeat(self) -> bool {
+        self.beat == 1
+    }
+}
+
+pub const SUBDIVISIONS: [u32; 6] = [1, 2, 3, 4, 5, 6];
+pub const MIN_SWING: f64 = 50.0;
+pub const MAX_SWING: f64 = 75.0;
+/// Amount one key press changes a mixer level by.
+pub const LEVEL_STEP: f32 = 0.05;
+
+/// A level the volume keys can adjust.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Channel {
+    Master,
+    Accent,
+    Beat,
+    Subdivision,
+    Count,
+    Drone,
+}
+
+impl Channel {
+    pub const ALL: [Self; 6] = [
+        Self::Master,
+        Self::Accent,
+        Self::Beat,
+        Self::Subdivision,
+        Self::Count,
+        Self::Drone,
+    ];
+
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Master => Self::Accent,
+            Self::Accent => Self::Beat,
+            Self::Beat => Self::Subdivision,
+            Self::Subdivision => Self::Count,
+            Self::Count => Self::Drone,
+            Self::Drone => Self::Master,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Master => "Master",
+            Self::Accent => "Accent",
+            Self::Beat => "Beat",
+            Self::Subdivision => "Subdivision",
+            Self::Count => "Count",
+            Self::Drone => "Drone",
+        }
+    }
+}
+
+/// Stereo position of each click layer, from -1 (left) through 0 (centre) to 1 (right).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pan {
+    pub accent: f32,
+    pub beat: f32,
+    pub subdivision: f32,
+    /// The second layer of a polyrhythm or polymeter.
+    pub layer: f32,
+    pub count: f32,
+}
+
+impl FromStr for Pan {
+    type Err = String;
+
+    /// Parses a comma separated list such as `beat=-1,layer=1`. Layers left out stay centred.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pan = Self::default();
+        for setting in s.split(',').filter(|setting| !setting.trim().is_empty()) {
+            let (layer, value) = setting
+                .split_once('=')
+                .ok_or_else(|| format!("'{setting}' is not of the form <layer>=<pan>, e.g. layer=1"))?;
+            let value = value
+                .trim()
+                .parse::<f32>()
+                .map_err(|_| format!("Invalid pan for '{}' in '{s}'", layer.trim()))?;
+            if !(-1.0..=1.0).contains(&value) {
+                return Err(format!("Pan for '{}' in '{s}' must be between -1 and 1", layer.trim()));
+            }
+
+            match layer.trim() {
+                "accent" => pan.accent = value,
+                "beat" => pan.beat = value,
+                "subdivision" => pan.subdivision = value,
+                "layer" => pan.layer = value,
+                "count" => pan.count = value,
+                other => {
+                    return Err(format!(
+                        "Unknown pan layer '{other}', expected accent, beat, subdivision, layer or count"
+                    ));
+                }
+            }
+        }
+        Ok(pan)
+    }
+}
+
+/// Output levels from 0 to 1, applied on top of each voice's own level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mixer {
+    pub master: f32,
+    pub accent: f32,
+    pub beat: f32,
+    pub subdivision: f32,
+    /// The spoken count.
+    pub count: f32,
+    pub drone: f32,
+    pub pan: Pan,
+    /// Silences the output while the clock and display keep running.
+    pub muted: bool,
+}
+
+impl Mixer {
+    pub const fn new(master: f32, drone: f32, pan: Pan) -> Self {
+        Self {
+            master,
+            accent: 1.0,
+            beat: 1.0,
+            subdivision: 1.0,
+            count: 1.0,
+            drone,
+            pan,
+            muted: false,
+        }
+    }
+
+    pub const fn level(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Master => self.master,
+            Channel::Accent => self.accent,
+            Channel::Beat => self.beat,
+            Channel::Subdivision => self.subdivision,
+            Channel::Count => self.count,
+            Channel::Drone => self.drone,
+        }
+    }
+
+    pub const fn adjust(&mut self, channel: Channel, delta: f32) {
+        let level = match channel {
+            Channel::Master => &mut self.master,
+            Channel::Accent => &mut self.accent,
+            Channel::Beat => &mut self.beat,
+            Channel::Subdivision => &mut self.subdivision,
+            Channel::Count => &mut self.count,
+            Channel::Drone => &mut self.drone,
+        };
+        *level = (*level + delta).clamp(0.0, 1.0);
+    }
+
+    /// Left and right gain a click is played with. Group accents follow the accent level,
+    /// and ghost notes and the second layer follow the beat level. Panning only turns the
+    /// far side down, so a centred click plays at full level on both sides.
+    pub const fn gain(&self, click: Click) -> (f32, f32) {
+        if self.muted {
+            return (0.0, 0.0);
+        }
+        let (level, pan) = match click {
+            Click::Accent | Click::GroupAccent => (self.accent, self.pan.accent),
+            Click::Beat | Click::Ghost => (self.beat, self.pan.beat),
+            Click::Layer => (self.beat, self.pan.layer),
+            Click::Subdivision => (self.subdivision, self.pan.subdivision),
+            Click::Count(_) => (self.count, self.pan.count),
+        };
+        let gain = self.master * level;
+        (gain * (1.0 - pan).min(1.0), gain * (1.0 + pan).min(1.0))
+    }
+
+    /// Level the drone plays at, centred.
+    pub const fn drone_gain(&self) -> f32 {
+        if self.muted { 0.0 } else { self.master * self.drone }
+    }
+}
+
+/// Playback options that can be changed live from the UI while the metronome runs.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub subdivision: u32,
+    /// Share of each pair of subdivisions given to the first click, in percent.
+    /// 50 plays straight, 66.7 a triplet swing and 75 a hard shuffle.
+    pub swing: f64,
+    /// Accent of each beat, one pattern per bar of the bar sequence. The engine picks up
+    /// edits at the next downbeat of that bar.
+    pub accents: Vec<Vec<BeatAccent>>,
+    pub displacement: Displacement,
+    /// Index into `PATTERNS` of the rhythm pattern to play instead of the plain pulse.
+    pub pattern: Option<usize>,
+    pub mixer: Mixer,
+    pub count: Count,
+    pub drone: Drone,
+    /// Audio output latency in milliseconds. The display runs this much behind the audio
+    /// it belongs to so the two line up.
+    pub latency: f64,
+}
+
+impl Settings {
+    pub const fn new(
+        subdivision: u32,
+        swing: f64,
+        accents: Vec<Vec<BeatAccent>>,
+        displacement: Displacement,
+        pattern: Option<usize>,
+        mixer: Mixer,
+        latency: f64,
+    ) -> Self {
+        Self {
+            subdivision,
+            swing,
+            accents,
+            displacement,
+            pattern,
+            mixer,
+            count: Count::Off,
+            drone: Drone::A440,
+            latency,
+        }
+    }
+
+    /// Steps the displacement one subdivision later, using eighths when the beat is not
+    /// subdivided.
+    pub const fn cycle_displacement(&mut self) {
+        let grid = if self.subdivision > 1 { self.subdivision } else { 2 };
+        self.displacement = self.displacement.next(grid);
+    }
+
+    pub fn cycle_accent(&mut self, bar_index: usize, beat_index: usize) {
+        if let Some(accent) = self
+            .accents
+            .get_mut(bar_index)
+            .and_then(|bar| bar.get_mut(beat_index))
+        {
+            *accent = accent.next();
+        }
+    }
+
+    pub const fn adjust_swing(&mut self, delta: f64) {
+        self.swing = (self.swing + delta).clamp(MIN_SWING, MAX_SWING);
+    }
+
+    /// The settings a single beat is laid out and mixed with.
+    pub const fn beat_settings(&self) -> BeatSettings {
+        BeatSettings {
+            subdivision: self.subdivision,
+            swing: self.swing,
+            displacement: self.displacement,
+            count: self.count,
+            mixer: self.mixer,
+            latency: self.latency,
+        }
+    }
+
+    pub fn cycle_subdivision(&mut self) {
+        let index = SUBDIVISIONS
+            .iter()
+            .position(|&s| s == self.subdivision)
+            .map_or(0, |i| (i + 1) % SUBDIVISIONS.len());
+        self.subdivision = SUBDIVISIONS[index];
+    }
+}
+
+/// The scalar settings of one beat, copied out of `Settings` in a single lock so the audio
+/// thread neither clones the accent patterns nor locks again for every click.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BeatSettings {
+    pub subdivision: u32,
+    pub swing: f64,
+    pub displacement: Displacement,
+    pub count: Count,
+    pub mixer: Mixer,
+    pub latency: f64,
+}
+
+impl BeatSettings {
+    /// Offset of subdivision click `index` within the beat, as a fraction of the beat.
+    /// Every second click is pushed back according to the swing setting.
+    pub fn subdivision_offset(&self, index: u32) -> f64 {
+        let subdivision = f64::from(self.subdivision);
+        if index % 2 == 1 {
+            (f64::from(index - 1) + 2.0 * self.swing / 100.0) / subdivision
+        } else {
+            f64::from(index) / subdivision
+        }
+    }
+}
+
+/// What the counting voice says on top of the clicks.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Count {
+    #[default]
+    Off,
+    /// The beat numbers.
+    Beats,
+    /// The beat numbers with "e", "and" and "a" on the subdivisions.
+    Subdivisions,
+}
+
+impl Count {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Beats,
+            Self::Beats => Self::Subdivisions,
+            Self::Subdivisions => Self::Off,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Beats => "beats",
+            Self::Subdivisions => "subdivisions",
+        }
+    }
+}
+
+pub const fn subdivision_name(subdivision: u32) -> &'static str {
+    match subdivision {
+        1 => "none",
+        2 => "eighths",
+        3 => "triplets",
+        4 => "sixteenths",
+        5 => "quintuplets",
+        6 => "sextuplets",
+        _ => "custom",
+    }
+}
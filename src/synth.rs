@@ -0,0 +1,305 @@
+use std::f64::consts::TAU;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Envelope level at which a decaying voice is cut off.
+const SILENCE: f64 = 0.001;
+/// Longest decay time constant a voice may be given, in seconds.
+const MAX_DECAY: f64 = 5.0;
+/// Highest pitch a synthesized voice may be given, in Hz.
+const MAX_PITCH: f64 = 20_000.0;
+/// Range of playback rates a `tick` may be pitched to.
+const TICK_RATES: std::ops::RangeInclusive<f64> = 0.25..=4.0;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum VoiceKind {
+    /// The bundled click sample, or a sample loaded from disk. Its pitch is a playback
+    /// rate rather than a frequency.
+    Tick,
+    Sine,
+    Square,
+    Woodblock,
+    Cowbell,
+    Rimshot,
+    HiHat,
+    Clack,
+}
+
+pub const VOICE_KINDS: [(&str, VoiceKind); 8] = [
+    ("tick", VoiceKind::Tick),
+    ("sine", VoiceKind::Sine),
+    ("square", VoiceKind::Square),
+    ("woodblock", VoiceKind::Woodblock),
+    ("cowbell", VoiceKind::Cowbell),
+    ("rimshot", VoiceKind::Rimshot),
+    ("hihat", VoiceKind::HiHat),
+    ("clack", VoiceKind::Clack),
+];
+
+impl VoiceKind {
+    /// Default pitch in Hz, or playback rate for `Tick`.
+    const fn default_pitch(self) -> f64 {
+        match self {
+            Self::Tick => 1.0,
+            Self::Sine => 1000.0,
+            Self::Square => 1500.0,
+            Self::Woodblock => 1100.0,
+            Self::Cowbell => 540.0,
+            Self::Rimshot => 1700.0,
+            Self::HiHat => 7000.0,
+            Self::Clack => 2200.0,
+        }
+    }
+
+    /// Default decay time constant in seconds. Unused by `Tick`.
+    const fn default_decay(self) -> f64 {
+        match self {
+            Self::Tick => 0.0,
+            Self::Sine => 0.03,
+            Self::Square => 0.02,
+            Self::Woodblock => 0.025,
+            Self::Cowbell => 0.12,
+            Self::Rimshot => 0.015,
+            Self::HiHat => 0.03,
+            Self::Clack => 0.008,
+        }
+    }
+}
+
+/// A click sound: the voice and its pitch, decay and level. Parameters left out fall
+/// back to the voice's defaults and the level to the role the voice is used for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoiceSpec {
+    pub kind: VoiceKind,
+    pub pitch: Option<f64>,
+    pub decay: Option<f64>,
+    pub level: Option<f32>,
+    /// Sample file played instead of the bundled tick.
+    pub sample: Option<PathBuf>,
+}
+
+impl VoiceSpec {
+    pub const fn new(kind: VoiceKind) -> Self {
+        Self {
+            kind,
+            pitch: None,
+            decay: None,
+            level: None,
+            sample: None,
+        }
+    }
+
+    /// A sample file from disk, played as recorded.
+    pub fn sample(path: PathBuf) -> Self {
+        Self {
+            sample: Some(path),
+            ..Self::new(VoiceKind::Tick)
+        }
+    }
+
+    pub fn pitch(&self) -> f64 {
+        self.pitch.unwrap_or_else(|| self.kind.default_pitch())
+    }
+
+    pub fn decay(&self) -> f64 {
+        self.decay.unwrap_or_else(|| self.kind.default_decay())
+    }
+
+    /// The same voice with its pitch multiplied by `ratio`.
+    pub fn pitched(&self, ratio: f64) -> Self {
+        Self {
+            pitch: Some(self.pitch() * ratio),
+            ..self.clone()
+        }
+    }
+
+    /// The same voice with `level` unless a level was set explicitly.
+    pub fn with_default_level(&self, level: f32) -> Self {
+        Self {
+            level: Some(self.level.unwrap_or(level)),
+            ..self.clone()
+        }
+    }
+}
+
+impl FromStr for VoiceSpec {
+    type Err = String;
+
+    /// Parses `<voice>[:pitch=<hz>,decay=<seconds>,level=<0-1>]`, e.g. `cowbell:pitch=600,level=0.7`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, params) = s.split_once(':').unwrap_or((s, ""));
+        let kind = VOICE_KINDS
+            .iter()
+            .find(|(voice, _)| voice.eq_ignore_ascii_case(name.trim()))
+            .map(|&(_, kind)| kind)
+            .ok_or_else(|| {
+                let names: Vec<&str> = VOICE_KINDS.iter().map(|(name, _)| *name).collect();
+                format!("Unknown voice '{name}', expected one of {}", names.join(", "))
+            })?;
+
+        let mut spec = Self::new(kind);
+        for param in params.split(',').filter(|param| !param.trim().is_empty()) {
+            let (key, value) = param
+                .split_once('=')
+                .ok_or_else(|| format!("Voice parameter '{param}' is not of the form <name>=<value>"))?;
+            let value = value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| format!("Invalid value for '{}' in '{s}'", key.trim()))?;
+
+            match key.trim() {
+                "pitch" | "decay" if value <= 0.0 => {
+                    return Err(format!("'{}' in '{s}' must be greater than zero", key.trim()));
+                }
+                "pitch" if kind == VoiceKind::Tick && !TICK_RATES.contains(&value) => {
+                    return Err(format!(
+                        "Pitch of a tick in '{s}' is a playback rate and must be between {} and {}",
+                        TICK_RATES.start(),
+                        TICK_RATES.end()
+                    ));
+                }
+                "pitch" if value > MAX_PITCH => {
+                    return Err(format!("Pitch in '{s}' must be at most {MAX_PITCH} Hz"));
+                }
+                "decay" if value > MAX_DECAY => {
+                    return Err(format!("Decay in '{s}' must be at most {MAX_DECAY} seconds"));
+                }
+                "pitch" => spec.pitch = Some(value),
+                "decay" => spec.decay = Some(value),
+                "level" if !(0.0..=1.0).contains(&value) => {
+                    return Err(format!("Level in '{s}' must be between 0 and 1"));
+                }
+                #[allow(clippy::cast_possible_truncation)]
+                "level" => spec.level = Some(value as f32),
+                other => {
+                    return Err(format!(
+                        "Unknown voice parameter '{other}', expected pitch, decay or level"
+                    ));
+                }
+            }
+        }
+        Ok(spec)
+    }
+}
+
+/// Renders a synthesized voice as mono samples at `sample_rate`, peaking at 1.0.
+/// `Tick` is sample based and renders nothing here.
+pub fn synthesize(kind: VoiceKind, pitch: f64, decay: f64, sample_rate: u32) -> Vec<f32> {
+    let rate = f64::from(sample_rate);
+    // Kept below the Nyquist frequency so high pitches do not alias.
+    let pitch = pitch.min(rate * 0.45);
+    // Long enough for the slowest envelope of the voice to fade out.
+    let length = decay * 2.0 * (1.0 / SILENCE).ln() + 0.005;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let frames = (length * rate) as usize;
+    let mut noise = Noise::new();
+    let mut filter = 0.0;
+
+    let samples: Vec<f64> = (0..frames)
+        .map(|frame| {
+            #[allow(clippy::cast_precision_loss)]
+            let t = frame as f64 / rate;
+            let env = (-t / decay).exp();
+            let phase = TAU * pitch * t;
+            match kind {
+                VoiceKind::Tick => 0.0,
+                VoiceKind::Sine => phase.sin() * env,
+                VoiceKind::Square => phase.sin().signum() * env * 0.5,
+                // A hollow body: the fundamental plus an inharmonic partial that dies faster.
+                VoiceKind::Woodblock => {
+                    phase.sin() * env + 0.4 * (phase * 2.76).sin() * (-t / (decay * 0.4)).exp()
+                }
+                // Two detuned square waves, smoothed by a one-pole low-pass.
+                VoiceKind::Cowbell => {
+                    let raw = 0.5 * (phase.sin().signum() + (phase * 1.48).sin().signum());
+                    filter += 0.35 * (raw - filter);
+                    filter * env
+                }
+                // A noise crack on top of a short high and low tone.
+                VoiceKind::Rimshot => {
+                    let crack = noise.next() * (-t / (decay * 0.5)).exp();
+                    let tone = (phase.sin() + 0.6 * (phase * 0.3).sin()) * env;
+                    0.6 * crack + 0.5 * tone
+                }
+                // White noise minus its one-pole low-pass; the pitch sets the cut-off.
+                VoiceKind::HiHat => {
+                    let white = noise.next();
+                    filter += (1.0 - (-TAU * pitch / rate).exp()) * (white - filter);
+                    (white - filter) * env
+                }
+                // The pendulum's escapement: a sharp click and a short ringing body.
+                VoiceKind::Clack => {
+                    let click = noise.next() * (-t / (decay * 0.3)).exp();
+                    let body = (phase.sin() + 0.5 * (phase * 1.57).sin()) * (-t / (decay * 2.0)).exp();
+                    0.7 * click + 0.6 * body
+                }
+            }
+        })
+        .collect();
+
+    normalize(&samples)
+}
+
+/// Applies an exponential fade with time constant `decay` seconds to existing samples.
+pub fn decayed(samples: &[f32], decay: f64, sample_rate: u32) -> Vec<f32> {
+    let rate = f64::from(sample_rate);
+    let length = decay * (1.0 / SILENCE).ln() * rate;
+    samples
+        .iter()
+        .enumerate()
+        .take_while(|&(frame, _)| {
+            #[allow(clippy::cast_precision_loss)]
+            let frame = frame as f64;
+            frame < length
+        })
+        .map(|(frame, sample)| {
+            #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+            let env = (-(frame as f64) / rate / decay).exp() as f32;
+            sample * env
+        })
+        .collect()
+}
+
+/// Plays existing samples back `rate` times faster, interpolating between them, so the
+/// pitch rises with the rate and the sound gets shorter.
+pub fn resampled(samples: &[f32], rate: f64) -> Vec<f32> {
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let frames = (samples.len() as f64 / rate) as usize;
+    (0..frames)
+        .map(|frame| {
+            #[allow(clippy::cast_precision_loss)]
+            let at = frame as f64 * rate;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let index = (at as usize).min(samples.len() - 1);
+            #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+            let fraction = (at - index as f64) as f32;
+            let next = samples.get(index + 1).copied().unwrap_or(0.0);
+            samples[index] + (next - samples[index]) * fraction
+        })
+        .collect()
+}
+
+fn normalize(samples: &[f64]) -> Vec<f32> {
+    let peak = samples.iter().fold(0.0_f64, |peak, sample| peak.max(sample.abs()));
+    let gain = if peak > 0.0 { 1.0 / peak } else { 0.0 };
+    #[allow(clippy::cast_possible_truncation)]
+    samples.iter().map(|sample| (sample * gain) as f32).collect()
+}
+
+/// Small deterministic xorshift generator, so noise voices sound the same every run.
+struct Noise(u32);
+
+impl Noise {
+    const fn new() -> Self {
+        Self(0x9E37_79B9)
+    }
+
+    fn next(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        f64::from(self.0) / f64::from(u32::MAX) * 2.0 - 1.0
+    }
+}
@@ -26,11 +26,11 @@ impl TapTempo {
     pub fn tap(&mut self) -> Option<f64> {
         let now = Instant::now();
         
-        if let Some(last_tap) = self.tap_times.last() {
-            if now.duration_since(*last_tap) > self.tap_timeout {
-                self.tap_times.clear();
-                self.is_tapping = false;
-            }
+        if let Some(last_tap) = self.tap_times.last()
+            && now.duration_since(*last_tap) > self.tap_timeout
+        {
+            self.tap_times.clear();
+            self.is_tapping = false;
         }
 
         self.tap_times.push(now);
@@ -5,38 +5,89 @@ use crossterm::{
 };
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, LineGauge, List, ListItem, ListState, Paragraph},
     Terminal,
 };
 use std::sync::{atomic::Ordering, Arc, Mutex};
-use std::time::Duration;
-use crate::state::{AtomicMetronomeState, MetronomeState};
+use std::time::{Duration, Instant};
+use crate::args::Args;
+use crate::calibration::{self, Calibration, CALIBRATION_TAPS};
+use crate::gap::GapProgress;
+use crate::ladder::{LadderArgs, LadderProgress};
+use crate::meter::{BeatAccent, Displacement, Polymeter, Polyrhythm, TimeSignature};
+use crate::output::{self, Output, OutputStatus};
+use crate::patterns::{Pattern, PATTERNS};
+use crate::ramp::RampProgress;
+use crate::state::{
+    subdivision_name, AtomicMetronomeState, Channel, MetronomeState, Mixer, Position, Settings,
+    LEVEL_STEP, MIN_SWING,
+};
 use crate::tap_tempo::TapTempo;
 
 pub struct AppState {
     current_bpm: f64,
     state: MetronomeState,
+    settings: Settings,
+    position: Position,
+    bars: Vec<TimeSignature>,
+    loop_bars: bool,
+    /// Tempos of the progressive ramp and how many times it plays.
+    ramp_tempos: Vec<f64>,
+    ramp_cycles: u32,
+    ladder: Option<LadderArgs>,
+    polyrhythm: Option<Polyrhythm>,
+    polymeter: Option<Polymeter>,
+    accent_cursor: usize,
+    /// Mixer level the volume keys adjust.
+    channel: Channel,
     tap_tempo: TapTempo,
     input_mode: bool,
     input_buffer: String,
+    /// Highlighted row of the open pattern picker. Row 0 is the plain pulse, row `i + 1`
+    /// is `PATTERNS[i]`.
+    picker: Option<usize>,
+    output_status: OutputStatus,
+    /// Output devices found when the device picker was opened.
+    devices: Vec<String>,
+    /// Highlighted row of the open device picker.
+    device_picker: Option<usize>,
+    /// Latency calibration in progress.
+    calibration: Option<Calibration>,
+    /// Outcome of the last calibration.
+    calibration_message: Option<String>,
 }
 
 impl AppState {
+    fn current_bar(&self) -> TimeSignature {
+        self.position.pattern.map_or_else(
+            || self.bars[self.position.sequence_bar].clone(),
+            |index| PATTERNS[index].time_signature(),
+        )
+    }
+
     fn handle_key_event(
         &mut self,
         bpm_shared: &Arc<Mutex<f64>>,
         state: &AtomicMetronomeState,
+        settings: &Mutex<Settings>,
+        output: &Output,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if event::poll(Duration::from_millis(16))? {
-            if let Event::Key(key) = event::read()? {
-                if self.input_mode {
-                    self.handle_input_mode(key, bpm_shared);
-                } else {
-                    self.handle_normal_mode(key, bpm_shared, state);
-                }
+        if event::poll(Duration::from_millis(16))?
+            && let Event::Key(key) = event::read()?
+        {
+            if self.input_mode {
+                self.handle_input_mode(key, bpm_shared);
+            } else if let Some(row) = self.picker {
+                self.handle_picker_mode(key, row, settings);
+            } else if let Some(row) = self.device_picker {
+                self.handle_device_picker_mode(key, row, output);
+            } else if self.calibration.is_some() {
+                self.handle_calibration_mode(key, settings);
+            } else {
+                self.handle_normal_mode(key, bpm_shared, state, settings);
             }
         }
         Ok(())
@@ -47,6 +98,7 @@ impl AppState {
         key: crossterm::event::KeyEvent,
         bpm_shared: &Arc<Mutex<f64>>,
         state: &AtomicMetronomeState,
+        settings: &Mutex<Settings>,
     ) {
         match key.code {
             KeyCode::Char('k' | 'K') => {
@@ -84,6 +136,77 @@ impl AppState {
                     self.current_bpm = bpm;
                 }
             }
+            KeyCode::Char('s' | 'S') => {
+                let mut settings = settings.lock().unwrap();
+                settings.cycle_subdivision();
+                self.settings = settings.clone();
+            }
+            KeyCode::Char('n' | 'N') => {
+                let mut settings = settings.lock().unwrap();
+                settings.count = settings.count.next();
+                self.settings = settings.clone();
+            }
+            KeyCode::Char('t' | 'T') => {
+                let mut settings = settings.lock().unwrap();
+                settings.drone.playing = !settings.drone.playing;
+                self.settings = settings.clone();
+            }
+            KeyCode::Char('[' | ']') => {
+                let delta = if key.code == KeyCode::Char(']') { 1.0 } else { -1.0 };
+                let mut settings = settings.lock().unwrap();
+                settings.adjust_swing(delta);
+                self.settings = settings.clone();
+            }
+            KeyCode::Left | KeyCode::Char('h' | 'H') => {
+                self.accent_cursor = self.accent_cursor.saturating_sub(1);
+            }
+            KeyCode::Right | KeyCode::Char('l' | 'L') => {
+                let last = self.current_bar().beats as usize - 1;
+                self.accent_cursor = (self.accent_cursor + 1).min(last);
+            }
+            KeyCode::Char('a' | 'A') if self.position.pattern.is_none() => {
+                let mut settings = settings.lock().unwrap();
+                settings.cycle_accent(self.position.sequence_bar, self.accent_cursor);
+                self.settings = settings.clone();
+            }
+            KeyCode::Char('d' | 'D') => {
+                let mut settings = settings.lock().unwrap();
+                settings.cycle_displacement();
+                self.settings = settings.clone();
+            }
+            KeyCode::Char('v' | 'V') => {
+                self.channel = self.channel.next();
+            }
+            KeyCode::Char('-' | '_' | '=' | '+') => {
+                let delta = if matches!(key.code, KeyCode::Char('=' | '+')) {
+                    LEVEL_STEP
+                } else {
+                    -LEVEL_STEP
+                };
+                let mut settings = settings.lock().unwrap();
+                settings.mixer.adjust(self.channel, delta);
+                self.settings = settings.clone();
+            }
+            KeyCode::Char('m' | 'M') => {
+                let mut settings = settings.lock().unwrap();
+                settings.mixer.muted = !settings.mixer.muted;
+                self.settings = settings.clone();
+            }
+            KeyCode::Char('p' | 'P') => {
+                self.picker = Some(self.settings.pattern.map_or(0, |index| index + 1));
+            }
+            KeyCode::Char('o' | 'O') => {
+                self.devices = output::device_names();
+                let current = self
+                    .devices
+                    .iter()
+                    .position(|device| *device == self.output_status.device);
+                self.device_picker = Some(current.unwrap_or(0));
+            }
+            KeyCode::Char('c' | 'C') => {
+                self.calibration = Some(Calibration::new());
+                self.calibration_message = None;
+            }
             KeyCode::Char('i' | 'I') | KeyCode::Enter => {
                 self.input_mode = true;
                 self.input_buffer.clear();
@@ -92,6 +215,90 @@ impl AppState {
         }
     }
 
+    fn handle_picker_mode(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        row: usize,
+        settings: &Mutex<Settings>,
+    ) {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k' | 'K') => {
+                self.picker = Some(row.saturating_sub(1));
+            }
+            KeyCode::Down | KeyCode::Char('j' | 'J') => {
+                self.picker = Some((row + 1).min(PATTERNS.len()));
+            }
+            KeyCode::Enter => {
+                let mut settings = settings.lock().unwrap();
+                settings.pattern = row.checked_sub(1);
+                self.settings = settings.clone();
+                self.picker = None;
+            }
+            KeyCode::Esc | KeyCode::Char('p' | 'P') => {
+                self.picker = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_device_picker_mode(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        row: usize,
+        output: &Output,
+    ) {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k' | 'K') => {
+                self.device_picker = Some(row.saturating_sub(1));
+            }
+            KeyCode::Down | KeyCode::Char('j' | 'J') => {
+                self.device_picker = Some((row + 1).min(self.devices.len().saturating_sub(1)));
+            }
+            KeyCode::Enter => {
+                if let Some(device) = self.devices.get(row) {
+                    output.switch(device.clone());
+                }
+                self.device_picker = None;
+            }
+            KeyCode::Esc | KeyCode::Char('o' | 'O') => {
+                self.device_picker = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_calibration_mode(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        settings: &Mutex<Settings>,
+    ) {
+        let Some(calibration) = &mut self.calibration else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                calibration.tap(Instant::now());
+                if !calibration.is_complete() {
+                    return;
+                }
+                let mut settings = settings.lock().unwrap();
+                if let Some(latency) = calibration.latency(settings.latency) {
+                    settings.latency = latency;
+                    self.calibration_message = Some(match calibration::save_latency(latency) {
+                        Ok(()) => format!("Latency set to {latency:.0} ms and saved"),
+                        Err(e) => format!("Latency set to {latency:.0} ms but not saved: {e}"),
+                    });
+                }
+                self.settings = settings.clone();
+                self.calibration = None;
+            }
+            KeyCode::Esc => {
+                self.calibration = None;
+            }
+            _ => {}
+        }
+    }
+
     fn handle_input_mode(
         &mut self,
         key: crossterm::event::KeyEvent,
@@ -99,14 +306,14 @@ impl AppState {
     ) {
         match key.code {
             KeyCode::Enter => {
-                if let Ok(bpm) = self.input_buffer.parse::<f64>() {
-                    if bpm > 0.0 {
-                        {
-                            let mut shared_bpm = bpm_shared.lock().unwrap();
-                            *shared_bpm = bpm;
-                        }
-                        self.current_bpm = bpm;
+                if let Ok(bpm) = self.input_buffer.parse::<f64>()
+                    && bpm > 0.0
+                {
+                    {
+                        let mut shared_bpm = bpm_shared.lock().unwrap();
+                        *shared_bpm = bpm;
                     }
+                    self.current_bpm = bpm;
                 }
                 self.input_mode = false;
                 self.input_buffer.clear();
@@ -129,7 +336,10 @@ impl AppState {
 pub async fn run(
     bpm_shared: Arc<Mutex<f64>>,
     state: Arc<AtomicMetronomeState>,
-    start_bpm: f64,
+    settings: Arc<Mutex<Settings>>,
+    position: Arc<Mutex<Position>>,
+    output: Output,
+    args: Args,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
@@ -138,11 +348,28 @@ pub async fn run(
     let mut terminal = Terminal::new(backend)?;
 
     let mut app_state = AppState {
-        current_bpm: start_bpm,
+        current_bpm: args.start_bpm,
         state: state.load(Ordering::SeqCst),
+        settings: settings.lock().unwrap().clone(),
+        position: Position::default(),
+        bars: args.bars.clone(),
+        loop_bars: args.loop_bars,
+        ramp_tempos: args.tempos.clone(),
+        ramp_cycles: args.cycles,
+        ladder: args.ladder,
+        polyrhythm: args.polyrhythm,
+        polymeter: args.polymeter,
+        accent_cursor: 0,
+        channel: Channel::Master,
         tap_tempo: TapTempo::new(),
         input_mode: false,
         input_buffer: String::new(),
+        picker: None,
+        output_status: output.status(),
+        devices: Vec::new(),
+        device_picker: None,
+        calibration: None,
+        calibration_message: None,
     };
 
     while app_state.state != MetronomeState::Stopped {
@@ -165,13 +392,27 @@ pub async fn run(
                 "".into()
             };
 
+            let swing_text = if app_state.settings.swing > MIN_SWING {
+                format!(" [SWING {:.0}%]", app_state.settings.swing).magenta()
+            } else {
+                "".into()
+            };
+
+            let muted_text = if app_state.output_status.silent {
+                " [SILENT]".red()
+            } else if app_state.settings.mixer.muted {
+                " [MUTED]".red()
+            } else {
+                "".into()
+            };
+
             let tap_text = if app_state.tap_tempo.is_tapping() {
                 format!(" [TAP: {}]", app_state.tap_tempo.get_tap_count()).yellow()
             } else {
                 "".into()
             };
 
-            let bpm_text = vec![
+            let mut bpm_text = vec![
                 Line::from(""),
                 Line::from(vec![
                     Span::styled(
@@ -179,17 +420,138 @@ pub async fn run(
                         Style::default().fg(Color::Green),
                     ),
                     Span::raw(" BPM  "),
+                    swing_text,
                     paused_text,
+                    muted_text,
                     tap_text,
                 ]),
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled(
+                        app_state.current_bar().to_string(),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::raw(format!(
+                        "  Bar {}, Beat {}",
+                        app_state.position.bar, app_state.position.beat
+                    )),
+                ]),
+                Line::from(vec![
+                    Span::raw("Subdivision: "),
+                    Span::styled(
+                        format!(
+                            "{} ({})",
+                            app_state.settings.subdivision,
+                            subdivision_name(app_state.settings.subdivision)
+                        ),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::raw("  Count: "),
+                    Span::styled(
+                        app_state.settings.count.name(),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                ]),
             ];
 
+            let drone = app_state.settings.drone;
+            if drone.playing {
+                bpm_text.push(Line::from(vec![
+                    Span::raw("Drone: "),
+                    Span::styled(
+                        format!(
+                            "{} ({:.1} Hz, {}, A4 = {} Hz)",
+                            drone.note,
+                            drone.frequency(),
+                            drone.waveform.name(),
+                            drone.reference
+                        ),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                ]));
+            }
+            if let Some(progress) = app_state.position.ramp {
+                bpm_text.push(ramp_line(
+                    &app_state.ramp_tempos,
+                    app_state.ramp_cycles,
+                    progress,
+                ));
+            }
+            if let (Some(ladder), Some(progress)) = (&app_state.ladder, app_state.position.ladder) {
+                bpm_text.push(ladder_line(ladder, progress));
+            }
+            if let Some(progress) = app_state.position.gap {
+                bpm_text.push(gap_line(progress));
+            }
+            if let Some(index) = app_state.position.pattern {
+                bpm_text.extend(pattern_lines(&PATTERNS[index], app_state.position));
+            } else {
+                bpm_text.push(beat_line(
+                    &app_state.current_bar(),
+                    &app_state.settings.accents[app_state.position.sequence_bar],
+                    app_state.position,
+                    app_state.accent_cursor,
+                ));
+            }
+            if !app_state.settings.displacement.is_none() {
+                bpm_text.push(displacement_line(app_state.settings.displacement));
+            }
+            if app_state.bars.len() > 1 {
+                bpm_text.push(sequence_line(
+                    &app_state.bars,
+                    app_state.position.sequence_bar,
+                    app_state.loop_bars,
+                ));
+            }
+            if let Some(polyrhythm) = app_state.polyrhythm {
+                bpm_text.extend(polyrhythm_lines(polyrhythm, app_state.position));
+            }
+            if let Some(polymeter) = app_state.polymeter {
+                bpm_text.extend(polymeter_lines(
+                    polymeter,
+                    &app_state.current_bar(),
+                    app_state.position,
+                ));
+            }
+            if let Some(calibration) = &app_state.calibration {
+                bpm_text.push(Line::from(""));
+                bpm_text.push(Line::from(format!(
+                    "Calibrating latency: tap Space with the click you hear ({}/{CALIBRATION_TAPS}), Esc to cancel",
+                    calibration.taps()
+                ).yellow()));
+            } else if let Some(message) = &app_state.calibration_message {
+                bpm_text.push(Line::from(""));
+                bpm_text.push(Line::from(message.clone().green()));
+            }
+            if let Some(error) = &app_state.output_status.error {
+                bpm_text.push(Line::from(""));
+                bpm_text.push(Line::from(error.clone().red()));
+            }
+
             let bpm_block = Paragraph::new(bpm_text).centered().block(
                 Block::default()
                     .borders(Borders::ALL)
                     .title(Line::from(" Metronome ".blue().bold()).centered()),
             );
-            f.render_widget(bpm_block, chunks[0]);
+            let main_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(8)].as_ref())
+                .split(chunks[0]);
+            f.render_widget(bpm_block, main_chunks[0]);
+            render_mixer(f, main_chunks[1], app_state.settings.mixer, app_state.channel);
+
+            if let Some(row) = app_state.picker {
+                render_picker(f, chunks[0], row, app_state.settings.pattern);
+            }
+            if let Some(row) = app_state.device_picker {
+                render_device_picker(
+                    f,
+                    chunks[0],
+                    row,
+                    &app_state.devices,
+                    &app_state.output_status.device,
+                );
+            }
 
             // Render input field if in input mode
             if app_state.input_mode {
@@ -229,6 +591,36 @@ pub async fn run(
                     "<G>".blue(),
                     " Manual Input: ".into(),
                     "<I>".blue(),
+                    " Subdivision: ".into(),
+                    "<S>".blue(),
+                    " Swing: ".into(),
+                    "<[> <]>".blue(),
+                    " Count: ".into(),
+                    "<N>".blue(),
+                ]).centered(),
+                Line::from(vec![
+                    "Select Beat: ".into(),
+                    "<H> <L>".blue(),
+                    " Cycle Accent: ".into(),
+                    "<A>".blue(),
+                    " Displace: ".into(),
+                    "<D>".blue(),
+                    " Patterns: ".into(),
+                    "<P>".blue(),
+                    " Output: ".into(),
+                    "<O>".blue(),
+                ]).centered(),
+                Line::from(vec![
+                    "Select Level: ".into(),
+                    "<V>".blue(),
+                    " Volume: ".into(),
+                    "<-> <+>".blue(),
+                    " Mute: ".into(),
+                    "<M>".blue(),
+                    " Calibrate Latency: ".into(),
+                    "<C>".blue(),
+                    " Drone: ".into(),
+                    "<T>".blue(),
                 ]).centered(),
             ];
 
@@ -244,12 +636,363 @@ pub async fn run(
         if let Ok(new_bpm) = bpm_shared.lock() {
             app_state.current_bpm = *new_bpm;
         }
+        if let Ok(new_position) = position.lock() {
+            if let Some(calibration) = &mut app_state.calibration
+                && (new_position.bar, new_position.beat)
+                    != (app_state.position.bar, app_state.position.beat)
+            {
+                calibration.on_beat(Instant::now());
+            }
+            app_state.position = *new_position;
+        }
+        let last_beat = app_state.current_bar().beats as usize - 1;
+        app_state.accent_cursor = app_state.accent_cursor.min(last_beat);
+        if let Ok(new_settings) = settings.lock() {
+            app_state.settings = new_settings.clone();
+        }
+
+        app_state.output_status = output.status();
 
         app_state.state = state.load(Ordering::SeqCst);
-        app_state.handle_key_event(&bpm_shared, &state)?;
+        app_state.handle_key_event(&bpm_shared, &state, &settings, &output)?;
     }
 
     disable_raw_mode()?;
     execute!(std::io::stdout(), LeaveAlternateScreen)?;
     Ok(())
 }
+
+/// Draws one cell per beat of the bar, split into the meter's groups. Each cell shows
+/// the beat's accent, the beat last played is highlighted and the editor cursor is boxed.
+fn beat_line(
+    time_signature: &TimeSignature,
+    accents: &[BeatAccent],
+    position: Position,
+    cursor: usize,
+) -> Line<'static> {
+    let mut spans = Vec::new();
+    for (index, beat) in (1..=time_signature.beats).enumerate() {
+        if time_signature.is_group_start(beat) && beat > 1 {
+            spans.push(Span::raw("| ").dark_gray());
+        }
+        let symbol = match accents.get(index) {
+            Some(BeatAccent::Accent) => "█",
+            Some(BeatAccent::Normal) | None => "▅",
+            Some(BeatAccent::Ghost) => "▂",
+            Some(BeatAccent::Mute) => "·",
+        };
+        let style = if beat == position.beat {
+            Style::default().fg(Color::Yellow).bold()
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        let (open, close) = if index == cursor { ("[", "]") } else { (" ", " ") };
+        spans.push(Span::raw(open).cyan());
+        spans.push(Span::styled(symbol, style));
+        spans.push(Span::raw(close).cyan());
+    }
+    Line::from(spans)
+}
+
+/// Shows the tempos of the progressive ramp with the segment being played highlighted,
+/// followed by the segment and cycle counts.
+fn ramp_line(tempos: &[f64], cycles: u32, progress: RampProgress) -> Line<'static> {
+    let active = Style::default().fg(Color::Yellow).bold();
+    let mut spans = vec![Span::raw("Ramp ")];
+    for (index, bpm) in tempos.iter().enumerate() {
+        let in_segment = index == progress.segment || index == progress.segment + 1;
+        if index > 0 {
+            spans.push(if index == progress.segment + 1 {
+                Span::styled(" → ", active)
+            } else {
+                Span::raw(" → ").dark_gray()
+            });
+        }
+        let text = format!("{bpm}");
+        spans.push(if in_segment { Span::styled(text, active) } else { Span::raw(text).dark_gray() });
+    }
+    spans.push(Span::raw(format!(
+        "  Segment {} of {}",
+        progress.segment + 1,
+        tempos.len() - 1
+    )));
+    spans.push(Span::raw(match cycles {
+        0 => format!("  Cycle {}", progress.cycle + 1),
+        1 => String::new(),
+        cycles => format!("  Cycle {} of {cycles}", progress.cycle + 1),
+    }));
+    Line::from(spans).magenta()
+}
+
+/// Shows how far the speed-trainer ladder has climbed towards its target, with the peak
+/// of the current climb marked and the bar count of the current rung.
+fn ladder_line(ladder: &LadderArgs, progress: LadderProgress) -> Line<'static> {
+    const WIDTH: usize = 24;
+    let span = ladder.target - ladder.start_bpm;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+    let cell = |bpm: f64| (((bpm - ladder.start_bpm) / span).clamp(0.0, 1.0) * WIDTH as f64).round() as usize;
+    let (filled, peak) = (cell(progress.bpm), cell(progress.peak));
+
+    let mut spans = vec![Span::raw("Ladder ")];
+    spans.push(Span::raw("█".repeat(filled)).green());
+    if peak > filled {
+        spans.push(Span::raw("░".repeat(peak - filled - 1)).dark_gray());
+        spans.push(Span::raw("▏").yellow());
+    }
+    spans.push(Span::raw("░".repeat(WIDTH - filled.max(peak))).dark_gray());
+    spans.push(Span::raw(format!(
+        " {} / {} BPM  Peak {}  Climb {}  Bar {} of {}",
+        progress.bpm, ladder.target, progress.peak, progress.climb, progress.bar, ladder.bars
+    )));
+    Line::from(spans)
+}
+
+/// Shows whether the gap trainer has silenced the bar and how many bars until it switches.
+fn gap_line(progress: GapProgress) -> Line<'static> {
+    let bars = |n: u32| if n == 1 { "1 bar".to_string() } else { format!("{n} bars") };
+    if progress.silent {
+        Line::from(vec![
+            Span::styled(" SILENT ", Style::default().fg(Color::Black).bg(Color::Red).bold()),
+            Span::styled(
+                format!(" Click returns in {}", bars(progress.bars_left)),
+                Style::default().fg(Color::Red).bold(),
+            ),
+        ])
+    } else {
+        Line::from(
+            format!(
+                "Click for {} more, then {} silent",
+                bars(progress.bars_left),
+                bars(progress.gap)
+            )
+            .dark_gray(),
+        )
+    }
+}
+
+/// Shows one beat split into the displacement grid, marking the real beat that the
+/// counter follows and the point where the displaced click sounds.
+fn displacement_line(displacement: Displacement) -> Line<'static> {
+    let mut spans = vec![Span::raw(format!("Displaced {displacement}  ")).magenta()];
+    spans.extend((0..displacement.grid).map(|cell| {
+        if cell == 0 {
+            Span::styled("▼ ", Style::default().fg(Color::Yellow).bold())
+        } else if cell == displacement.steps {
+            Span::styled("♪ ", Style::default().fg(Color::Magenta).bold())
+        } else {
+            Span::raw("· ").dark_gray()
+        }
+    }));
+    spans.push(Span::raw(" ▼ real beat  ♪ click").dark_gray());
+    Line::from(spans)
+}
+
+/// Shows the rhythm pattern on its grid, one group of cells per beat, with the beat
+/// being played highlighted.
+fn pattern_lines(pattern: &Pattern, position: Position) -> Vec<Line<'static>> {
+    let grid = pattern.grid as usize;
+    let mut spans = Vec::new();
+    for (beat, cells) in pattern.hits.as_bytes().chunks(grid).enumerate() {
+        let cells: String = cells
+            .iter()
+            .map(|&cell| match cell {
+                b'X' => '●',
+                b'x' => '○',
+                _ => '·',
+            })
+            .collect();
+        let style = if beat + 1 == position.beat as usize {
+            Style::default().fg(Color::Yellow).bold()
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        spans.push(Span::styled(cells, style));
+        spans.push(Span::raw(" "));
+    }
+
+    vec![
+        Line::from(format!("Pattern {}", pattern.name).magenta()),
+        Line::from(spans),
+    ]
+}
+
+/// Draws the pattern picker as a popup over `area`, marking the pattern in use.
+fn render_picker(f: &mut ratatui::Frame, area: Rect, row: usize, current: Option<usize>) {
+    let mut items = vec![ListItem::new(format!(
+        "{} plain pulse",
+        if current.is_none() { "*" } else { " " }
+    ))];
+    items.extend(PATTERNS.iter().enumerate().map(|(index, pattern)| {
+        let marker = if current == Some(index) { "*" } else { " " };
+        ListItem::new(format!(
+            "{marker} {:<16} {}",
+            pattern.name,
+            pattern.time_signature()
+        ))
+    }));
+
+    let popup = popup_area(area, 40, items.len());
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(" Patterns ".cyan().bold()).centered()),
+        )
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+    let mut list_state = ListState::default().with_selected(Some(row));
+
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(list, popup, &mut list_state);
+}
+
+/// Draws one gauge per mixer level, marking the level the volume keys adjust. The
+/// gauges dim while the output is muted.
+fn render_mixer(f: &mut ratatui::Frame, area: Rect, mixer: Mixer, selected: Channel) {
+    let title = if mixer.muted { " Mixer (muted) " } else { " Mixer " };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Line::from(title.yellow().bold()).centered());
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1); Channel::ALL.len()].as_ref())
+        .split(inner);
+    for (channel, row) in Channel::ALL.into_iter().zip(rows.iter()) {
+        let level = mixer.level(channel);
+        let color = if mixer.muted { Color::DarkGray } else { Color::Green };
+        let marker = if channel == selected { ">" } else { " " };
+        let gauge = LineGauge::default()
+            .label(format!("{marker} {:<12}{:>4.0}%", channel.name(), level * 100.0))
+            .ratio(f64::from(level))
+            .filled_style(Style::default().fg(color))
+            .unfilled_style(Style::default().fg(Color::DarkGray));
+        f.render_widget(gauge, *row);
+    }
+}
+
+/// Draws the output device picker as a popup over `area`, marking the device in use.
+fn render_device_picker(
+    f: &mut ratatui::Frame,
+    area: Rect,
+    row: usize,
+    devices: &[String],
+    current: &str,
+) {
+    let items: Vec<ListItem> = if devices.is_empty() {
+        vec![ListItem::new("  no output devices found")]
+    } else {
+        devices
+            .iter()
+            .map(|device| {
+                let marker = if device == current { "*" } else { " " };
+                ListItem::new(format!("{marker} {device}"))
+            })
+            .collect()
+    };
+    let popup = popup_area(area, 60, items.len());
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(" Output Devices ".cyan().bold()).centered()),
+        )
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+    let mut list_state = ListState::default().with_selected(Some(row));
+
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(list, popup, &mut list_state);
+}
+
+/// A box of at most `width` columns, tall enough for `rows` list items, centred in `area`.
+fn popup_area(area: Rect, width: u16, rows: usize) -> Rect {
+    #[allow(clippy::cast_possible_truncation)]
+    let height = (rows as u16 + 2).min(area.height);
+    let width = width.min(area.width);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Lists the bars of a mixed-meter sequence, highlighting the one being played.
+fn sequence_line(bars: &[TimeSignature], current: usize, loop_bars: bool) -> Line<'static> {
+    let mut spans: Vec<Span> = bars
+        .iter()
+        .enumerate()
+        .map(|(index, bar)| {
+            if index == current {
+                Span::styled(format!(" {bar} "), Style::default().fg(Color::Black).bg(Color::Cyan))
+            } else {
+                Span::styled(format!(" {bar} "), Style::default().fg(Color::DarkGray))
+            }
+        })
+        .collect();
+    spans.push(Span::raw(if loop_bars { "  (loop)" } else { "  (once)" }));
+    Line::from(spans)
+}
+
+/// Draws both polyrhythm layers on a shared grid, highlighting the pulse each layer last played.
+fn polyrhythm_lines(polyrhythm: Polyrhythm, position: Position) -> Vec<Line<'static>> {
+    let cells = polyrhythm.grid_cells();
+    let layer = |name: &'static str, pulses: u32, current: u32, color: Color| {
+        let step = cells / pulses;
+        let mut spans = vec![Span::raw(format!("{name} {pulses:>2}  "))];
+        spans.extend((0..cells).map(|cell| {
+            if cell % step != 0 {
+                Span::raw("· ")
+            } else if cell / step + 1 == current {
+                Span::styled("● ", Style::default().fg(color).bold())
+            } else {
+                Span::styled("○ ", Style::default().fg(color))
+            }
+        }));
+        Line::from(spans)
+    };
+
+    vec![
+        Line::from(""),
+        Line::from(format!("Polyrhythm {polyrhythm}").magenta()),
+        layer("A", polyrhythm.main, position.beat, Color::Green),
+        layer("B", polyrhythm.cross, position.cross_pulse, Color::Magenta),
+    ]
+}
+
+/// Shows where the polymeter layer is in its own bar and how long until both downbeats meet.
+fn polymeter_lines(
+    polymeter: Polymeter,
+    time_signature: &TimeSignature,
+    position: Position,
+) -> Vec<Line<'static>> {
+    let mut layer = vec![Span::raw(format!("B {:>2}  ", polymeter.beats))];
+    layer.extend((1..=polymeter.beats).map(|beat| {
+        let symbol = if beat == 1 { "● " } else { "• " };
+        if beat == position.layer_beat {
+            Span::styled(symbol, Style::default().fg(Color::Magenta).bold())
+        } else {
+            Span::styled(symbol, Style::default().fg(Color::DarkGray))
+        }
+    }));
+
+    let alignment = if position.bar == 0 {
+        Line::from("")
+    } else {
+        match polymeter.beats_to_alignment(time_signature.beats, position.beat, position.layer_beat) {
+            0 => Line::from("Downbeats aligned".green().bold()),
+            1 => Line::from("Downbeats align in 1 beat".yellow()),
+            beats => Line::from(format!("Downbeats align in {beats} beats")),
+        }
+    };
+
+    vec![
+        Line::from(""),
+        Line::from(format!("Polymeter {} against {}", time_signature.beats, polymeter.beats).magenta()),
+        Line::from(layer),
+        alignment,
+    ]
+}